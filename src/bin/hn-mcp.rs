@@ -1,18 +1,221 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use hn_mcp::tools::{hn::client::HnClient, HnRouter};
+use axum_server::tls_rustls::RustlsConfig;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use hn_mcp::tools::{
+    hn::{
+        client::{
+            timezone::set_display_timezone, HnClient, DEFAULT_CACHE_SIZE,
+            DEFAULT_MAX_IN_FLIGHT_REQUESTS,
+        },
+        format::FormatOptions,
+        DEFAULT_MAX_COMMENT_COUNT, DEFAULT_MAX_CONCURRENCY, DEFAULT_MAX_SCAN_COUNT,
+        DEFAULT_MAX_STORY_COUNT,
+    },
+    HnRouter,
+};
+use hn_mcp::transport::sse_server::{normalize_path_prefix, SseServerOptions};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use tracing_subscriber::{self, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 #[derive(Parser)]
-#[command(author, version = "0.1.0", about = "HN MCP Server", long_about = None)]
+#[command(author, version = env!("CARGO_PKG_VERSION"), about = "HN MCP Server", long_about = None)]
 #[command(propagate_version = true)]
-#[command(disable_version_flag = true)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Options shared by every server mode
+#[derive(Args)]
+struct RouterArgs {
+    /// Upper bound for the `chunk_size` parameter of the story list tools
+    #[arg(long, default_value_t = DEFAULT_MAX_CONCURRENCY)]
+    max_concurrency: usize,
+
+    /// Upper bound for the `count` of the story list tools and hn_digest, and the IDs of hn_stories_by_ids
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_STORY_COUNT)]
+    max_story_count: usize,
+
+    /// Upper bound for the `count` of the feed-scanning tools and the IDs of hn_prefetch
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_SCAN_COUNT)]
+    max_scan_count: usize,
+
+    /// Upper bound for the comments of hn_story_with_comments and hn_best_comments
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_COMMENT_COUNT)]
+    max_comment_count: usize,
+
+    /// Disable the story cache so every read returns fresh scores and comment counts
+    #[arg(long, conflicts_with = "cache_file")]
+    no_cache: bool,
+
+    /// Load the story cache from this JSON file at startup and save it back on shutdown
+    #[arg(long, value_name = "PATH", env = "HN_MCP_CACHE_FILE")]
+    cache_file: Option<PathBuf>,
+
+    /// Load --cache-file but never write it, e.g. for a pre-seeded file in demos and tests
+    #[arg(long, requires = "cache_file")]
+    read_only_cache: bool,
+
+    /// Upper bound for HN API requests in flight at once, shared by all clients
+    #[arg(long, default_value_t = DEFAULT_MAX_IN_FLIGHT_REQUESTS)]
+    max_in_flight_requests: usize,
+
+    /// Start at most this many HN API requests per second, shared by all clients [default: unlimited]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    max_requests_per_second: Option<u32>,
+
+    /// User-Agent header sent to the HN APIs [default: hn-mcp/<version>]
+    #[arg(long, env = "HN_MCP_USER_AGENT")]
+    user_agent: Option<String>,
+
+    /// Truncate story list output at a story boundary once it exceeds this many bytes
+    #[arg(long, value_name = "BYTES")]
+    max_response_bytes: Option<usize>,
+
+    /// Show dates in this IANA time zone (e.g. Asia/Tokyo) instead of UTC
+    #[arg(long, env = "HN_MCP_TIMEZONE")]
+    timezone: Option<String>,
+
+    /// Line between stories in text output; `\f`, `\t` and `\n` are unescaped and an empty value leaves a blank line
+    #[arg(long, value_name = "SEP", default_value = "---", value_parser = parse_separator)]
+    record_separator: String,
+
+    /// Print story values without their `Title:`/`URL:`/... labels, one per line in a fixed order
+    #[arg(long)]
+    no_field_labels: bool,
+
+    /// Server instructions sent to clients instead of the built-in usage guide
+    #[arg(long, value_name = "TEXT", conflicts_with = "instructions_file")]
+    instructions: Option<String>,
+
+    /// Read the server instructions from this file instead of using the built-in usage guide
+    #[arg(long, value_name = "PATH", env = "HN_MCP_INSTRUCTIONS_FILE")]
+    instructions_file: Option<PathBuf>,
+
+    /// Hide a tool from clients and reject calls to it (repeatable, e.g. --disable-tool hn_raw_item)
+    #[arg(long = "disable-tool", value_name = "TOOL")]
+    disabled_tools: Vec<String>,
+
+    /// Expose only this tool (repeatable); every other tool is disabled
+    #[arg(long = "enable-tool", value_name = "TOOL")]
+    enabled_tools: Vec<String>,
+}
+
+// Shells make control characters awkward to pass, so accept the usual escapes
+fn parse_separator(value: &str) -> Result<String, String> {
+    let mut separator = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            separator.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('f') => separator.push('\x0c'),
+            Some('t') => separator.push('\t'),
+            Some('n') => separator.push('\n'),
+            Some('\\') => separator.push('\\'),
+            Some(other) => return Err(format!("unsupported escape \\{}", other)),
+            None => return Err("trailing backslash".to_string()),
+        }
+    }
+    Ok(separator)
+}
+
+impl RouterArgs {
+    fn build_client(&self) -> Result<HnClient> {
+        let cache_size = if self.no_cache { 0 } else { DEFAULT_CACHE_SIZE };
+        let hn_client = HnClient::try_with_cache_size(cache_size)
+            .map_err(|e| anyhow::anyhow!("Failed to initialize the HN API client: {}", e))?;
+        let mut hn_client = hn_client.with_max_in_flight_requests(self.max_in_flight_requests);
+        if let Some(requests_per_second) = self.max_requests_per_second {
+            hn_client = hn_client.with_max_requests_per_second(requests_per_second);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            hn_client = hn_client.with_user_agent(user_agent)?;
+        }
+        Ok(hn_client)
+    }
+
+    // A missing cache file is normal on the first run; a broken one only costs a cold cache
+    async fn load_cache(&self, hn_client: &HnClient) {
+        let Some(cache_file) = &self.cache_file else {
+            return;
+        };
+        if !cache_file.exists() {
+            tracing::info!(
+                "Cache file {} does not exist yet; starting with an empty cache",
+                cache_file.display()
+            );
+            return;
+        }
+        if let Err(e) = hn_client.load_cache_file(cache_file).await {
+            tracing::warn!("{}; starting with an empty cache", e);
+        }
+    }
+
+    async fn save_cache(&self, hn_client: &HnClient) {
+        let Some(cache_file) = self.cache_file.as_ref().filter(|_| !self.read_only_cache) else {
+            return;
+        };
+        if let Err(e) = hn_client.save_cache_file(cache_file).await {
+            tracing::error!("{}", e);
+        }
+    }
+
+    // A file that cannot be read is a startup error; blank instructions keep the built-in ones
+    fn load_instructions(&self) -> Result<Option<String>> {
+        let instructions = match (&self.instructions, &self.instructions_file) {
+            (Some(instructions), _) => instructions.clone(),
+            (None, Some(path)) => std::fs::read_to_string(path).map_err(|e| {
+                anyhow::anyhow!("Failed to read instructions file {}: {}", path.display(), e)
+            })?,
+            (None, None) => return Ok(None),
+        };
+        if instructions.trim().is_empty() {
+            tracing::warn!("Custom instructions are empty; using the built-in instructions");
+            return Ok(None);
+        }
+        Ok(Some(instructions))
+    }
+
+    fn build_router(&self, hn_client: HnClient) -> Result<HnRouter> {
+        if let Some(timezone) = &self.timezone {
+            if let Err(e) = set_display_timezone(timezone) {
+                tracing::warn!("{}; showing dates in UTC", e);
+            }
+        }
+        let mut router = HnRouter::new(hn_client)
+            .with_max_concurrency(self.max_concurrency)
+            .with_max_story_count(self.max_story_count)
+            .with_max_scan_count(self.max_scan_count)
+            .with_max_comment_count(self.max_comment_count)
+            .with_format_options(FormatOptions {
+                separator: self.record_separator.clone(),
+                field_labels: !self.no_field_labels,
+            });
+        if let Some(max_response_bytes) = self.max_response_bytes {
+            router = router.with_max_response_bytes(max_response_bytes);
+        }
+        if let Some(instructions) = self.load_instructions()? {
+            router = router.with_instructions(instructions);
+        }
+        router
+            .with_enabled_tools(&self.enabled_tools)?
+            .with_disabled_tools(&self.disabled_tools)
+    }
+}
+
+/// Output format of the logs
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line, for log pipelines
+    Json,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Run the server in stdin/stdout mode
@@ -20,30 +223,80 @@ enum Commands {
         /// Enable debug logging
         #[arg(short, long)]
         debug: bool,
+
+        /// Log output format
+        #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+        log_format: LogFormat,
+
+        #[command(flatten)]
+        router: RouterArgs,
     },
     /// Run the server with HTTP/SSE interface
     Http {
-        /// Address to bind the HTTP server to
-        #[arg(short, long, default_value = "0.0.0.0:3000")]
-        address: String,
+        #[command(flatten)]
+        http: HttpArgs,
 
         /// Enable debug logging
         #[arg(short, long)]
         debug: bool,
+
+        /// Log output format
+        #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+        log_format: LogFormat,
+
+        #[command(flatten)]
+        router: RouterArgs,
     },
 }
 
+/// Options of the HTTP/SSE server
+#[derive(Args)]
+struct HttpArgs {
+    /// Address to bind the HTTP server to
+    #[arg(short, long, default_value = "0.0.0.0:3000")]
+    address: String,
+
+    /// Require `Authorization: Bearer <TOKEN>` on every HTTP request
+    #[arg(long, env = "HN_MCP_AUTH_TOKEN", hide_env_values = true)]
+    auth_token: Option<String>,
+
+    /// PEM certificate chain to serve HTTPS (requires --tls-key)
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key to serve HTTPS (requires --tls-cert)
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Expose Prometheus metrics at GET /metrics
+    #[arg(long)]
+    metrics: bool,
+
+    /// Serve the SSE and message endpoints under this base path (e.g. /hn)
+    #[arg(long)]
+    path_prefix: Option<String>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Stdio { debug } => run_stdio_server(debug).await,
-        Commands::Http { address, debug } => run_http_server(address, debug).await,
+        Commands::Stdio {
+            debug,
+            log_format,
+            router,
+        } => run_stdio_server(router, debug, log_format).await,
+        Commands::Http {
+            http,
+            debug,
+            log_format,
+            router,
+        } => run_http_server(http, router, debug, log_format).await,
     }
 }
 
-async fn run_stdio_server(debug: bool) -> Result<()> {
+async fn run_stdio_server(router: RouterArgs, debug: bool, log_format: LogFormat) -> Result<()> {
     // Initialize the tracing subscriber with stderr logging
     let level = if debug {
         tracing::Level::DEBUG
@@ -51,25 +304,51 @@ async fn run_stdio_server(debug: bool) -> Result<()> {
         tracing::Level::INFO
     };
 
-    tracing_subscriber::fmt()
+    let subscriber = tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env().add_directive(level.into()))
         .with_writer(std::io::stderr) // Explicitly use stderr for logging
         .with_target(false)
         .with_thread_ids(true)
         .with_file(true)
         .with_line_number(true)
-        .with_ansi(false) // Disable ANSI color codes
-        .init();
+        .with_ansi(false); // Disable ANSI color codes
+    match log_format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
 
     tracing::info!("Starting HN MCP server in STDIN/STDOUT mode");
 
+    let hn_client = router.build_client()?;
+    router.load_cache(&hn_client).await;
+
     // Run the server using the implementation
-    hn_mcp::transport::stdio::run_stdio_server()
+    hn_mcp::transport::stdio::run_stdio_server(router.build_router(hn_client.clone())?)
         .await
-        .map_err(|e| anyhow::anyhow!("Error running STDIO server: {}", e))
+        .map_err(|e| anyhow::anyhow!("Error running STDIO server: {}", e))?;
+
+    router.save_cache(&hn_client).await;
+
+    // After Ctrl+C or SIGTERM, tokio's stdin reader is still blocked in a read that only
+    // returns at EOF, and the runtime would wait for it on shutdown
+    std::process::exit(0)
 }
 
-async fn run_http_server(address: String, debug: bool) -> Result<()> {
+async fn run_http_server(
+    http: HttpArgs,
+    router: RouterArgs,
+    debug: bool,
+    log_format: LogFormat,
+) -> Result<()> {
+    let HttpArgs {
+        address,
+        auth_token,
+        tls_cert,
+        tls_key,
+        metrics,
+        path_prefix,
+    } = http;
+
     // Setup tracing
     let level = if debug { "debug" } else { "info" };
 
@@ -78,23 +357,77 @@ async fn run_http_server(address: String, debug: bool) -> Result<()> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| format!("{},{}", level, env!("CARGO_CRATE_NAME")).into()),
         )
-        .with(tracing_subscriber::fmt::layer().with_ansi(false)) // Disable ANSI color codes
+        // Only one of the two layers is present
+        .with(
+            (log_format == LogFormat::Text)
+                .then(|| tracing_subscriber::fmt::layer().with_ansi(false)),
+        ) // Disable ANSI color codes
+        .with((log_format == LogFormat::Json).then(|| tracing_subscriber::fmt::layer().json()))
         .init();
 
     // Parse socket address
     let addr: SocketAddr = address.parse()?;
 
+    // Load the TLS certificate and key up front so bad paths fail before binding
+    let tls_config = match (tls_cert, tls_key) {
+        (Some(cert), Some(key)) => Some(RustlsConfig::from_pem_file(&cert, &key).await.map_err(
+            |e| {
+                anyhow::anyhow!(
+                    "Failed to load TLS certificate {} and key {}: {}",
+                    cert.display(),
+                    key.display(),
+                    e
+                )
+            },
+        )?),
+        (None, None) => None,
+        _ => anyhow::bail!("--tls-cert and --tls-key must be provided together"),
+    };
+    let scheme = if tls_config.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let path_prefix = normalize_path_prefix(path_prefix.as_deref().unwrap_or_default());
+
     tracing::debug!("HN MCP Server listening on {}", addr);
-    tracing::info!("Access the HN MCP Server at http://{}/sse", addr);
+    tracing::info!(
+        "Access the HN MCP Server at {}://{}{}/sse",
+        scheme,
+        addr,
+        path_prefix
+    );
+    if auth_token.is_some() {
+        tracing::info!("Bearer token authentication is enabled");
+    }
+    if metrics {
+        tracing::info!(
+            "Prometheus metrics available at {}://{}/metrics",
+            scheme,
+            addr
+        );
+    }
 
     // Create and run server
-    let service = HnRouter::new(HnClient::new());
-    let server = hn_mcp::transport::sse_server::serve(service, addr.port())
-        .await
-        .map_err(|e| anyhow::anyhow!("Error starting SSE server: {}", e))?;
+    let hn_client = router.build_client()?;
+    router.load_cache(&hn_client).await;
+    let service = router.build_router(hn_client.clone())?;
+    let server = hn_mcp::transport::sse_server::serve(
+        service,
+        addr,
+        SseServerOptions {
+            auth_token,
+            tls_config,
+            metrics,
+            path_prefix,
+        },
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!("Error starting SSE server: {}", e))?;
 
     // Wait for server to complete
     let _ = server.await?;
 
+    router.save_cache(&hn_client).await;
     Ok(())
 }
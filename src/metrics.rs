@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// Process-wide counters, exposed in Prometheus text format by the HTTP server's `/metrics`
+/// endpoint when it is enabled. Counting is always on since it only costs a few atomic ops.
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct ToolCounters {
+    calls: u64,
+    errors: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    tools: Mutex<BTreeMap<&'static str, ToolCounters>>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+impl Metrics {
+    pub fn record_tool_call(&self, tool: &'static str, is_error: bool) {
+        let mut tools = self.tools.lock().unwrap_or_else(|e| e.into_inner());
+        let counters = tools.entry(tool).or_default();
+        counters.calls += 1;
+        if is_error {
+            counters.errors += 1;
+        }
+    }
+
+    pub fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_cache_miss(&self) {
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Render all counters in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let tools = self.tools.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut output = String::new();
+
+        // Writing to a String cannot fail
+        let _ = writeln!(
+            output,
+            "# HELP hn_mcp_tool_calls_total Number of tool calls, by tool."
+        );
+        let _ = writeln!(output, "# TYPE hn_mcp_tool_calls_total counter");
+        for (tool, counters) in &tools {
+            let _ = writeln!(
+                output,
+                "hn_mcp_tool_calls_total{{tool=\"{}\"}} {}",
+                tool, counters.calls
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP hn_mcp_tool_errors_total Number of tool calls that returned an error, by tool."
+        );
+        let _ = writeln!(output, "# TYPE hn_mcp_tool_errors_total counter");
+        for (tool, counters) in &tools {
+            let _ = writeln!(
+                output,
+                "hn_mcp_tool_errors_total{{tool=\"{}\"}} {}",
+                tool, counters.errors
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP hn_mcp_cache_hits_total Number of story reads served from the cache."
+        );
+        let _ = writeln!(output, "# TYPE hn_mcp_cache_hits_total counter");
+        let _ = writeln!(
+            output,
+            "hn_mcp_cache_hits_total {}",
+            self.cache_hits.load(Ordering::Relaxed)
+        );
+
+        let _ = writeln!(
+            output,
+            "# HELP hn_mcp_cache_misses_total Number of story reads that had to hit the API."
+        );
+        let _ = writeln!(output, "# TYPE hn_mcp_cache_misses_total counter");
+        let _ = writeln!(
+            output,
+            "hn_mcp_cache_misses_total {}",
+            self.cache_misses.load(Ordering::Relaxed)
+        );
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        metrics.record_tool_call("hn_top_stories", false);
+        metrics.record_tool_call("hn_top_stories", true);
+        metrics.record_tool_call("hn_user", false);
+        metrics.record_cache_hit();
+
+        let output = metrics.render();
+        let lines: Vec<&str> = output.lines().collect();
+        for expected in [
+            "# TYPE hn_mcp_tool_calls_total counter",
+            "hn_mcp_tool_calls_total{tool=\"hn_top_stories\"} 2",
+            "hn_mcp_tool_calls_total{tool=\"hn_user\"} 1",
+            "# TYPE hn_mcp_tool_errors_total counter",
+            "hn_mcp_tool_errors_total{tool=\"hn_top_stories\"} 1",
+            "hn_mcp_tool_errors_total{tool=\"hn_user\"} 0",
+            "hn_mcp_cache_hits_total 1",
+            "hn_mcp_cache_misses_total 0",
+        ] {
+            assert!(
+                lines.contains(&expected),
+                "missing line {:?} in:\n{}",
+                expected,
+                output
+            );
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::{bail, Result};
+use newswrap::items::stories::HackerNewsStory;
+use serde::Deserialize;
+use std::collections::HashMap;
+use time::OffsetDateTime;
+
+use super::item::HnItem;
+
+// Base URL of the Algolia-powered Hacker News search API
+pub const ALGOLIA_API_BASE_URL: &str = "https://hn.algolia.com/api/v1";
+
+// Result order of a search: `/search` ranks by relevance, `/search_by_date` by submission time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchSort {
+    Relevance,
+    Date,
+}
+
+impl SearchSort {
+    pub fn parse(sort: Option<&str>) -> Result<Self> {
+        match sort.map(str::trim).unwrap_or("relevance").to_ascii_lowercase().as_str() {
+            "" | "relevance" => Ok(Self::Relevance),
+            "date" => Ok(Self::Date),
+            other => bail!("Unsupported sort '{}' (expected 'relevance' or 'date')", other),
+        }
+    }
+
+    pub fn endpoint(self) -> &'static str {
+        match self {
+            Self::Relevance => "search",
+            Self::Date => "search_by_date",
+        }
+    }
+}
+
+// An attribute Algolia can count matching hits by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFacet {
+    Author,
+    Tags,
+}
+
+impl SearchFacet {
+    // Comma-separated facet names, e.g. `author,tags`; duplicates are dropped
+    pub fn parse_list(facets: Option<&str>) -> Result<Vec<Self>> {
+        let mut parsed = Vec::new();
+        for name in facets.unwrap_or_default().split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let facet = match name.to_ascii_lowercase().as_str() {
+                "author" => Self::Author,
+                "tags" => Self::Tags,
+                other => bail!("Unsupported facet '{}' (expected 'author' or 'tags')", other),
+            };
+            if !parsed.contains(&facet) {
+                parsed.push(facet);
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub fn attribute(self) -> &'static str {
+        match self {
+            Self::Author => "author",
+            Self::Tags => "_tags",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Author => "Top authors",
+            Self::Tags => "Top tags",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlgoliaSearchResponse {
+    pub hits: Vec<AlgoliaHit>,
+    #[serde(rename = "nbHits", default)]
+    pub nb_hits: u64,
+    // Counts over all matching hits, not just this page, keyed by attribute and then value;
+    // only present when facets were requested
+    #[serde(default)]
+    pub facets: HashMap<String, HashMap<String, u64>>,
+}
+
+// A single search hit; which fields are present depends on the item type
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlgoliaHit {
+    #[serde(rename = "objectID")]
+    pub object_id: String,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub author: Option<String>,
+    pub points: Option<u32>,
+    pub num_comments: Option<u32>,
+    pub created_at_i: Option<i64>,
+    // Text posts only (Ask HN and the like)
+    pub story_text: Option<String>,
+    // Comment hits only
+    pub comment_text: Option<String>,
+    pub story_id: Option<u32>,
+    pub story_title: Option<String>,
+}
+
+impl AlgoliaHit {
+    // Algolia stores item IDs as strings
+    pub fn item_id(&self) -> Option<u32> {
+        self.object_id.parse().ok()
+    }
+
+    // Convert a story hit to a story so it can be formatted like Firebase stories;
+    // `None` for comments and other item types
+    pub fn to_story(&self) -> Option<HackerNewsStory> {
+        if self.comment_text.is_some() {
+            return None;
+        }
+        Some(HackerNewsStory {
+            id: self.item_id()?,
+            title: self.title.clone()?,
+            url: self.url.clone().unwrap_or_default(),
+            text: self.story_text.clone().unwrap_or_default(),
+            by: self.author.clone().unwrap_or_default(),
+            score: self.points.unwrap_or(0),
+            created_at: self
+                .created_at_i
+                .and_then(|time| OffsetDateTime::from_unix_timestamp(time).ok())
+                .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+            number_of_comments: self.num_comments.unwrap_or(0),
+            comments: Vec::new(),
+        })
+    }
+}
+
+// An item with its whole comment tree, as returned by `/items/<id>`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlgoliaItem {
+    pub id: u32,
+    #[serde(rename = "type")]
+    pub item_type: Option<String>,
+    pub author: Option<String>,
+    pub text: Option<String>,
+    pub created_at_i: Option<i64>,
+    pub parent_id: Option<u32>,
+    #[serde(default)]
+    pub children: Vec<AlgoliaItem>,
+}
+
+impl AlgoliaItem {
+    // Algolia keeps deleted and flagged comments in the tree without author or text
+    pub fn is_visible(&self) -> bool {
+        self.author.is_some() && self.text.is_some()
+    }
+
+    // Convert to the Firebase item shape so comments can be formatted the same way
+    pub fn to_hn_item(&self) -> HnItem {
+        HnItem {
+            id: self.id,
+            item_type: self.item_type.clone(),
+            by: self.author.clone(),
+            time: self.created_at_i,
+            text: self.text.clone(),
+            deleted: false,
+            dead: !self.is_visible(),
+            parent: self.parent_id,
+            kids: self.children.iter().map(|child| child.id).collect(),
+            url: None,
+            score: None,
+            title: None,
+            descendants: None,
+            parts: Vec::new(),
+        }
+    }
+}
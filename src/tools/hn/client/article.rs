@@ -0,0 +1,205 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::{redirect, Url};
+
+use super::html::html_to_text;
+
+// Linked articles are arbitrary sites, so give up on slow ones quickly
+pub const ARTICLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Stop reading an article after this many bytes; the excerpt comes from the start anyway
+pub const MAX_ARTICLE_BYTES: usize = 1024 * 1024;
+
+// Characters of article text shown after the story
+pub const ARTICLE_EXCERPT_CHARS: usize = 500;
+
+// Redirects followed before an article fetch gives up, the same as reqwest's default
+const MAX_ARTICLE_REDIRECTS: usize = 10;
+
+// Elements whose content is never article text
+const SKIPPED_ELEMENTS: [&str; 8] = ["head", "script", "style", "noscript", "svg", "nav", "header", "footer"];
+
+// Only HTML pages are read; PDFs, images and the like are skipped
+pub fn is_html_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.eq_ignore_ascii_case("text/html") || mime.eq_ignore_ascii_case("application/xhtml+xml")
+}
+
+// Story URLs are user-submitted, so article requests must not reach the server's own
+// network: loopback, private, link-local, unique-local and unspecified addresses are refused
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                let unique_local = first & 0xfe00 == 0xfc00;
+                let link_local = first & 0xffc0 == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+// Reject URLs that are not http(s) or, unless `allow_private`, whose host is a non-public IP
+// literal. Host names are checked when they are resolved, by `PublicResolver`.
+pub fn check_article_url(url: &Url, allow_private: bool) -> Result<()> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow!("Unsupported article URL scheme '{}'", url.scheme()));
+    }
+    let host = url.host_str().ok_or_else(|| anyhow!("Article URL {} has no host", url))?;
+    if allow_private {
+        return Ok(());
+    }
+    if let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        if !is_public_ip(ip) {
+            return Err(anyhow!("Refusing to fetch article from non-public address {}", ip));
+        }
+    }
+    Ok(())
+}
+
+// Resolves host names like the system resolver, but drops non-public addresses and fails
+// if none are left, so a host name cannot smuggle a request into a private network
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs = tokio::net::lookup_host((host, 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect::<Vec<SocketAddr>>();
+            if addrs.is_empty() {
+                return Err(format!("{} does not resolve to a public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+// HTTP client for article fetches: only public addresses, checked on every redirect hop, and
+// no proxy, which would resolve host names itself. `allow_private` turns the address checks
+// off for tests against a local mock server.
+pub fn build_article_client(user_agent: &str, allow_private: bool) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().user_agent(user_agent).no_proxy();
+    if !allow_private {
+        builder = builder
+            .dns_resolver(Arc::new(PublicResolver))
+            .redirect(redirect::Policy::custom(|attempt| {
+                if attempt.previous().len() >= MAX_ARTICLE_REDIRECTS {
+                    return attempt.error("too many redirects");
+                }
+                match check_article_url(attempt.url(), false) {
+                    Ok(()) => attempt.follow(),
+                    Err(e) => attempt.error(e.to_string()),
+                }
+            }));
+    }
+    builder
+        .build()
+        .map_err(|e| anyhow!("Failed to build article HTTP client with user agent {:?}: {}", user_agent, e))
+}
+
+// Readable text of an HTML page on a single line: scripts, styles and page chrome are
+// dropped, the rest goes through `html_to_text`
+pub fn extract_article_text(html: &str) -> String {
+    let mut body = html.to_string();
+    for name in SKIPPED_ELEMENTS {
+        body = remove_elements(&body, name);
+    }
+    html_to_text(&body).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Remove every `<name ...>...</name>` element; an unclosed one removes the rest of the page
+fn remove_elements(html: &str, name: &str) -> String {
+    // ASCII lowercasing keeps byte offsets, so positions found here apply to `html`
+    let lower = html.to_ascii_lowercase();
+    let open = format!("<{}", name);
+    let close = format!("</{}", name);
+    let mut result = String::with_capacity(html.len());
+    let mut pos = 0;
+
+    while let Some(found) = lower[pos..].find(&open) {
+        let start = pos + found;
+        let after = lower[start + open.len()..].chars().next();
+        // `<header` must not match `<head`
+        if !matches!(after, Some(c) if c == '>' || c == '/' || c.is_whitespace()) {
+            result.push_str(&html[pos..start + open.len()]);
+            pos = start + open.len();
+            continue;
+        }
+        result.push_str(&html[pos..start]);
+        pos = match lower[start..].find(&close) {
+            Some(end) => {
+                let end = start + end;
+                lower[end..].find('>').map(|gt| end + gt + 1).unwrap_or(lower.len())
+            }
+            None => lower.len(),
+        };
+    }
+
+    result.push_str(&html[pos..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_article_text() {
+        let html = r#"<!DOCTYPE html><html><HEAD><title>Post</title><style>p { color: red }</style></HEAD>
+            <body><header><a href="/">Home</a></header>
+            <script>var x = "<p>not text</p>";</script>
+            <h1>Why SQLite</h1><p>It is   small &amp; fast.</p><p>See <a href="https://sqlite.org">the site</a>.</p>
+            <footer>Copyright</footer></body></html>"#;
+        assert_eq!(
+            extract_article_text(html),
+            "Why SQLite It is small & fast. See the site (https://sqlite.org)."
+        );
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in ["93.184.216.34", "2606:2800:220:1::1"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_is_html_content_type() {
+        assert!(is_html_content_type("text/html; charset=utf-8"));
+        assert!(is_html_content_type("Application/XHTML+XML"));
+        assert!(!is_html_content_type("application/pdf"));
+        assert!(!is_html_content_type(""));
+    }
+}
@@ -0,0 +1,86 @@
+// HN returns `text` (and occasionally `title`) as HTML: paragraphs are separated by
+// `<p>`, links are `<a href>` elements and punctuation is entity-encoded. Convert it
+// to plain text that reads well in tool output.
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    // Start of the current link's text in `text`, and its decoded href
+    let mut open_link: Option<(usize, String)> = None;
+    let mut rest = html;
+
+    while let Some(pos) = rest.find(['<', '&']) {
+        text.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        if rest.starts_with('<') {
+            let Some(end) = rest.find('>') else {
+                break;
+            };
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            match tag_name(tag).as_str() {
+                "p" => text.push_str("\n\n"),
+                "br" => text.push('\n'),
+                "a" => open_link = Some((text.len(), attribute(tag, "href").map(html_to_text).unwrap_or_default())),
+                "/a" => {
+                    // HN shortens long link text, so show the full URL unless it is already there
+                    if let Some((start, href)) = open_link.take() {
+                        if !href.is_empty() && text[start..] != href {
+                            text.push_str(&format!(" ({})", href));
+                        }
+                    }
+                }
+                // Formatting tags (<i>, <pre>, <code>, ...) are dropped
+                _ => {}
+            }
+        } else {
+            match rest.find(';').filter(|&end| end <= 10).and_then(|end| Some((end, decode_entity(&rest[1..end])?))) {
+                Some((end, c)) => {
+                    text.push(c);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    text.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+    }
+
+    text.push_str(rest);
+    text
+}
+
+// Lowercased tag name, keeping the leading '/' of closing tags
+fn tag_name(tag: &str) -> String {
+    tag.split(|c: char| c.is_whitespace() || c == '>')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/')
+        .to_ascii_lowercase()
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!("{}=\"", name))? + name.len() + 2;
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
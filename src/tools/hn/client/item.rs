@@ -0,0 +1,50 @@
+use newswrap::HackerNewsID;
+use serde::Deserialize;
+
+// Base URL of the official Hacker News Firebase API
+pub const DEFAULT_HN_API_BASE_URL: &str = "https://hacker-news.firebaseio.com/v0";
+
+// Environment variable overriding the base URL (e.g. for a mock server or caching proxy)
+pub const HN_API_BASE_URL_ENV: &str = "HN_API_BASE_URL";
+
+// Raw item as returned by `/v0/item/<id>.json`. Unlike the typed newswrap items this
+// covers every item type (story, comment, job, poll, pollopt) and is cloneable.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HnItem {
+    pub id: HackerNewsID,
+    #[serde(rename = "type")]
+    pub item_type: Option<String>,
+    pub by: Option<String>,
+    pub time: Option<i64>,
+    pub text: Option<String>,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub dead: bool,
+    pub parent: Option<HackerNewsID>,
+    #[serde(default)]
+    pub kids: Vec<HackerNewsID>,
+    pub url: Option<String>,
+    pub score: Option<u32>,
+    pub title: Option<String>,
+    pub descendants: Option<u32>,
+    // Poll options of a poll, in display order
+    #[serde(default)]
+    pub parts: Vec<HackerNewsID>,
+}
+
+// User profile as returned by `/v0/user/<username>.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct HnUser {
+    pub id: String,
+    #[serde(default)]
+    pub karma: i64,
+    pub created: Option<i64>,
+}
+
+impl HnItem {
+    // Deleted and dead (flagged) items carry no useful content
+    pub fn is_visible(&self) -> bool {
+        !self.deleted && !self.dead
+    }
+}
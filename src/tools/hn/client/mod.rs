@@ -1,19 +1,41 @@
+use crate::metrics::metrics;
 use anyhow::{anyhow, Result};
 use lru::LruCache;
-use newswrap::client::HackerNewsClient;
 use newswrap::items::stories::HackerNewsStory;
 use newswrap::HackerNewsID;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::num::NonZeroUsize;
+use std::path::Path;
 use std::sync::Arc;
 use time::OffsetDateTime;
-use tokio::sync::Mutex;
-use tracing::{debug, error, info};
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn, Instrument};
+
+pub mod algolia;
+pub mod article;
+pub mod html;
+pub mod item;
+pub mod rate_limit;
+pub mod timezone;
 
 #[cfg(test)]
 mod tests;
 
-// Since HackerNewsStory doesn't implement Clone, we'll store the essential fields we need
-#[derive(Debug, Clone)]
+use algolia::{AlgoliaHit, AlgoliaItem, AlgoliaSearchResponse, SearchFacet, SearchSort, ALGOLIA_API_BASE_URL};
+use html::html_to_text;
+use item::{HnItem, HnUser, DEFAULT_HN_API_BASE_URL, HN_API_BASE_URL_ENV};
+use rate_limit::RateLimiter;
+use timezone::to_display_time;
+
+use super::format::FormatOptions;
+use super::text::truncate_chars;
+
+// Since HackerNewsStory doesn't implement Clone, we'll store the essential fields we need.
+// Also the entry format of the cache file (`save_cache_file` / `load_cache_file`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct CachedStory {
     id: HackerNewsID,
     title: String,
@@ -21,14 +43,15 @@ struct CachedStory {
     text: String,
     by: String,
     score: u32,
-    created_at_string: String,
+    #[serde(with = "time::serde::timestamp")]
+    created_at: OffsetDateTime,
     number_of_comments: u32,
     // Keep comments as empty vector since we don't use them directly
     comments: Vec<HackerNewsID>,
 }
 
-impl From<HackerNewsStory> for CachedStory {
-    fn from(story: HackerNewsStory) -> Self {
+impl From<&HackerNewsStory> for CachedStory {
+    fn from(story: &HackerNewsStory) -> Self {
         CachedStory {
             id: story.id,
             title: story.title.clone(),
@@ -36,7 +59,7 @@ impl From<HackerNewsStory> for CachedStory {
             text: story.text.clone(),
             by: story.by.clone(),
             score: story.score,
-            created_at_string: story.created_at.to_string(),
+            created_at: story.created_at,
             number_of_comments: story.number_of_comments,
             comments: story.comments.clone(),
         }
@@ -45,38 +68,102 @@ impl From<HackerNewsStory> for CachedStory {
 
 impl CachedStory {
     // Create a new HackerNewsStory from cached data
-    fn to_story(&self) -> Result<HackerNewsStory, anyhow::Error> {
-        // Parse the date string into OffsetDateTime (simplistic approach)
-        let created_at = match OffsetDateTime::parse(&self.created_at_string, &time::format_description::well_known::Rfc3339) {
-            Ok(dt) => dt,
-            Err(_) => OffsetDateTime::now_utc(), // Fallback to current time if parsing fails
-        };
-        
-        // Create a new story by copying the cached fields
-        Ok(HackerNewsStory {
+    fn to_story(&self) -> HackerNewsStory {
+        HackerNewsStory {
             id: self.id,
             title: self.title.clone(),
             url: self.url.clone(),
             text: self.text.clone(),
             by: self.by.clone(),
             score: self.score,
-            created_at,
+            created_at: self.created_at,
             number_of_comments: self.number_of_comments,
             comments: self.comments.clone(),
-        })
+        }
     }
 }
 
+// Convert a raw item into a story, rejecting other item types
+fn story_from_item(item: HnItem) -> Result<HackerNewsStory> {
+    if item.item_type.as_deref() != Some("story") {
+        return Err(anyhow!(
+            "Item with ID {} is not a story (type: {})",
+            item.id,
+            item.item_type.as_deref().unwrap_or("unknown")
+        ));
+    }
+
+    let created_at = item
+        .time
+        .and_then(|time| OffsetDateTime::from_unix_timestamp(time).ok())
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH);
+
+    Ok(HackerNewsStory {
+        id: item.id,
+        title: item.title.unwrap_or_default(),
+        url: item.url.unwrap_or_default(),
+        text: item.text.unwrap_or_default(),
+        by: item.by.unwrap_or_default(),
+        score: item.score.unwrap_or(0),
+        created_at,
+        number_of_comments: item.descendants.unwrap_or(0),
+        comments: item.kids,
+    })
+}
+
+// Number of stories kept in the cache unless configured otherwise
+pub const DEFAULT_CACHE_SIZE: usize = 100;
+
+// Default ceiling for HN API requests in flight at once, across all tool calls
+pub const DEFAULT_MAX_IN_FLIGHT_REQUESTS: usize = 20;
+
+// Threads with at least this many comments load their comments from Algolia's
+// `/items/<id>` endpoint in one request instead of one Firebase request per comment
+pub const LARGE_THREAD_COMMENTS: u32 = 100;
+
+// Longest parent chain `get_ancestors` follows; real threads rarely nest deeper
+pub const MAX_ANCESTOR_DEPTH: usize = 50;
+
+// Hits requested for the `front_page` tag; comfortably more than the 30 stories on the
+// front page, so the whole set is ranked before any limit is applied
+const FRONT_PAGE_HITS: usize = 100;
+
+// Characters of comment text shown per entry of a thread breadcrumb
+const BREADCRUMB_TEXT_CHARS: usize = 80;
+
+// Values listed per facet in a search summary
+const MAX_FACET_VALUES: usize = 5;
+
+// Sent on every request so upstream APIs can tell where the traffic comes from
+pub const DEFAULT_USER_AGENT: &str = concat!("hn-mcp/", env!("CARGO_PKG_VERSION"));
+
 pub struct HnClient {
-    client: Arc<HackerNewsClient>,
-    story_cache: Arc<Mutex<LruCache<HackerNewsID, CachedStory>>>,
+    api_base_url: Arc<str>,
+    algolia_base_url: Arc<str>,
+    http: reqwest::Client,
+    // Separate client for linked articles, restricted to public addresses
+    article_http: reqwest::Client,
+    // Only set by tests, to read articles from a local mock server
+    allow_private_article_hosts: bool,
+    // `None` when caching is disabled
+    story_cache: Option<Arc<Mutex<LruCache<HackerNewsID, CachedStory>>>>,
+    // Shared by all clones, so concurrent sessions together stay under the limit
+    request_limiter: Arc<Semaphore>,
+    // Optional requests-per-second ceiling, also shared by all clones
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl Clone for HnClient {
     fn clone(&self) -> Self {
         Self {
-            client: self.client.clone(),
+            api_base_url: self.api_base_url.clone(),
+            algolia_base_url: self.algolia_base_url.clone(),
+            http: self.http.clone(),
+            article_http: self.article_http.clone(),
+            allow_private_article_hosts: self.allow_private_article_hosts,
             story_cache: self.story_cache.clone(),
+            request_limiter: self.request_limiter.clone(),
+            rate_limiter: self.rate_limiter.clone(),
         }
     }
 }
@@ -88,132 +175,215 @@ impl Default for HnClient {
 }
 
 impl HnClient {
+    /// Create a client with the default cache size.
+    /// Panics if the HTTP client cannot be initialized; use [`HnClient::try_new`] to handle that.
     pub fn new() -> Self {
-        // Create a cache with capacity of 100 stories
-        let cache_size = NonZeroUsize::new(100).expect("Cache size must be non-zero");
-        Self {
-            client: Arc::new(HackerNewsClient::new()),
-            story_cache: Arc::new(Mutex::new(LruCache::new(cache_size))),
-        }
+        Self::with_cache_size(DEFAULT_CACHE_SIZE)
     }
-    
-    /// Set a custom cache size (for testing or special use cases)
+
+    /// Like [`HnClient::new`], but returns an error if the HTTP client (e.g. its TLS
+    /// backend) cannot be initialized
+    pub fn try_new() -> Result<Self> {
+        Self::try_with_cache_size(DEFAULT_CACHE_SIZE)
+    }
+
+    /// Set a custom cache size (for testing or special use cases).
+    /// A size of 0 disables caching so every read hits the API.
     pub fn with_cache_size(cache_size: usize) -> Self {
-        let cache_size = NonZeroUsize::new(cache_size.max(1)).expect("Cache size must be non-zero");
-        Self {
-            client: Arc::new(HackerNewsClient::new()),
-            story_cache: Arc::new(Mutex::new(LruCache::new(cache_size))),
+        Self::try_with_cache_size(cache_size).expect("failed to initialize the HN client")
+    }
+
+    /// Fallible version of [`HnClient::with_cache_size`]
+    pub fn try_with_cache_size(cache_size: usize) -> Result<Self> {
+        let story_cache =
+            NonZeroUsize::new(cache_size).map(|size| Arc::new(Mutex::new(LruCache::new(size))));
+
+        // Allow pointing the client at a mock server or caching proxy
+        let api_base_url = std::env::var(HN_API_BASE_URL_ENV)
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_HN_API_BASE_URL.to_string());
+
+        Ok(Self {
+            api_base_url: normalize_base_url(&api_base_url),
+            algolia_base_url: ALGOLIA_API_BASE_URL.into(),
+            http: build_http_client(DEFAULT_USER_AGENT)?,
+            article_http: article::build_article_client(DEFAULT_USER_AGENT, false)?,
+            allow_private_article_hosts: false,
+            story_cache,
+            request_limiter: Arc::new(Semaphore::new(DEFAULT_MAX_IN_FLIGHT_REQUESTS)),
+            rate_limiter: None,
+        })
+    }
+
+    /// Create a client without a story cache, for always-fresh scores and comment counts
+    pub fn with_cache_disabled() -> Self {
+        Self::with_cache_size(0)
+    }
+
+    /// Use a different base URL for the Hacker News API (e.g. a mock server).
+    /// Takes precedence over the `HN_API_BASE_URL` environment variable.
+    pub fn with_base_url(mut self, api_base_url: &str) -> Self {
+        self.api_base_url = normalize_base_url(api_base_url);
+        self
+    }
+
+    /// Use a different base URL for the Algolia HN search API (e.g. a mock server)
+    pub fn with_algolia_base_url(mut self, algolia_base_url: &str) -> Self {
+        self.algolia_base_url = normalize_base_url(algolia_base_url);
+        self
+    }
+
+    /// Identify requests with a custom `User-Agent` header instead of `hn-mcp/<version>`.
+    /// Fails if the value is not a valid header value.
+    pub fn with_user_agent(mut self, user_agent: &str) -> Result<Self> {
+        self.http = build_http_client(user_agent)?;
+        self.article_http = article::build_article_client(user_agent, self.allow_private_article_hosts)?;
+        Ok(self)
+    }
+
+    // Let article fetches reach local mock servers
+    #[cfg(test)]
+    fn with_private_article_hosts(mut self) -> Self {
+        self.article_http = article::build_article_client(DEFAULT_USER_AGENT, true).unwrap();
+        self.allow_private_article_hosts = true;
+        self
+    }
+
+    /// Limit the number of HN API requests (Firebase and Algolia) in flight at once, independent of each
+    /// call's `chunk_size`. Clones made before this call keep the previous limit.
+    pub fn with_max_in_flight_requests(mut self, max_in_flight: usize) -> Self {
+        self.request_limiter = Arc::new(Semaphore::new(max_in_flight.max(1)));
+        self
+    }
+
+    /// Start at most this many HN API requests per second, across all clones and
+    /// therefore all connected sessions. Clones made before this call are not limited.
+    pub fn with_max_requests_per_second(mut self, requests_per_second: u32) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(requests_per_second)));
+        self
+    }
+
+    // Wait for a free request slot (and, with a rate limit, for the next start time);
+    // the semaphore is never closed
+    async fn acquire_request_permit(&self) -> Result<SemaphorePermit<'_>> {
+        let permit = self
+            .request_limiter
+            .acquire()
+            .await
+            .map_err(|e| anyhow!("Request limiter closed: {}", e))?;
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
         }
+        Ok(permit)
     }
 
-    // Get top stories from Hacker News
-    pub async fn get_top_stories(&self, limit: Option<usize>) -> Result<Vec<HackerNewsID>> {
-        let stories = self.client.realtime.get_top_stories().await
-            .map_err(|e| anyhow!("Failed to fetch top stories: {}", e))?;
+    // Fetch a list of story IDs from one of the realtime feed endpoints
+    async fn get_story_ids(&self, endpoint: &str, feed_name: &str, limit: Option<usize>) -> Result<Vec<HackerNewsID>> {
+        let _permit = self.acquire_request_permit().await?;
+        let stories: Vec<HackerNewsID> = self
+            .http
+            .get(format!("{}/{}.json", self.api_base_url, endpoint))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch {}: {}", feed_name, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to fetch {}: {}", feed_name, e))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse {}: {}", feed_name, e))?;
 
         let limit = limit.unwrap_or(stories.len());
         Ok(stories.into_iter().take(limit).collect())
     }
 
+    // Get top stories from Hacker News
+    pub async fn get_top_stories(&self, limit: Option<usize>) -> Result<Vec<HackerNewsID>> {
+        self.get_story_ids("topstories", "top stories", limit).await
+    }
+
     // Get latest stories from Hacker News
     pub async fn get_latest_stories(&self, limit: Option<usize>) -> Result<Vec<HackerNewsID>> {
-        let stories = self.client.realtime.get_latest_stories().await
-            .map_err(|e| anyhow!("Failed to fetch latest stories: {}", e))?;
-
-        let limit = limit.unwrap_or(stories.len());
-        Ok(stories.into_iter().take(limit).collect())
+        self.get_story_ids("newstories", "latest stories", limit).await
     }
 
     // Get best stories from Hacker News
     pub async fn get_best_stories(&self, limit: Option<usize>) -> Result<Vec<HackerNewsID>> {
-        let stories = self.client.realtime.get_best_stories().await
-            .map_err(|e| anyhow!("Failed to fetch best stories: {}", e))?;
-
-        let limit = limit.unwrap_or(stories.len());
-        Ok(stories.into_iter().take(limit).collect())
+        self.get_story_ids("beststories", "best stories", limit).await
     }
 
     // Get ask HN stories
     pub async fn get_ask_stories(&self, limit: Option<usize>) -> Result<Vec<HackerNewsID>> {
-        let stories = self.client.realtime.get_ask_hacker_news_stories().await
-            .map_err(|e| anyhow!("Failed to fetch Ask HN stories: {}", e))?;
-
-        let limit = limit.unwrap_or(stories.len());
-        Ok(stories.into_iter().take(limit).collect())
+        self.get_story_ids("askstories", "Ask HN stories", limit).await
     }
 
     // Get show HN stories
     pub async fn get_show_stories(&self, limit: Option<usize>) -> Result<Vec<HackerNewsID>> {
-        let stories = self.client.realtime.get_show_hacker_news_stories().await
-            .map_err(|e| anyhow!("Failed to fetch Show HN stories: {}", e))?;
-
-        let limit = limit.unwrap_or(stories.len());
-        Ok(stories.into_iter().take(limit).collect())
+        self.get_story_ids("showstories", "Show HN stories", limit).await
     }
 
     // Get details for a single story by ID with caching
     pub async fn get_story_details(&self, id: HackerNewsID) -> Result<HackerNewsStory> {
         // Check if the story is in cache first
-        {
-            let mut cache = self.story_cache.lock().await;
+        if let Some(story_cache) = &self.story_cache {
+            let mut cache = story_cache.lock().await;
             if let Some(cached_story) = cache.get(&id) {
                 debug!("Cache hit for story ID: {}", id);
-                return cached_story.to_story();
+                metrics().record_cache_hit();
+                return Ok(cached_story.to_story());
             }
         }
         
         // If not in cache, fetch from API
         debug!("Cache miss for story ID: {}, fetching from API", id);
-        let story = self.client.items.get_story(id).await
-            .map_err(|e| anyhow!("Failed to fetch story with ID {}: {}", id, e))?;
+        metrics().record_cache_miss();
+        let story = story_from_item(self.get_item(id).await?)?;
         
         // Store in cache
-        {
-            let mut cache = self.story_cache.lock().await;
-            let cached_story = CachedStory::from(story);
-            
-            // We need to re-fetch the story because we've consumed it
-            match self.client.items.get_story(id).await {
-                Ok(story) => {
-                    // Put in cache anyway
-                    cache.put(id, cached_story);
-                    Ok(story)
-                }
-                Err(e) => {
-                    // Attempt to convert from cache as a fallback
-                    if let Ok(story) = cached_story.to_story() {
-                        Ok(story)
-                    } else {
-                        Err(anyhow!("Failed to fetch story with ID {}: {}", id, e))
-                    }
-                }
-            }
+        if let Some(story_cache) = &self.story_cache {
+            story_cache.lock().await.put(id, CachedStory::from(&story));
         }
+        Ok(story)
+    }
+
+    // Fetch a story fresh from the API and replace the cached copy. Returns the previously
+    // cached copy as well (if any) so callers can compare scores and comment counts.
+    pub async fn refresh_story_details(&self, id: HackerNewsID) -> Result<(Option<HackerNewsStory>, HackerNewsStory)> {
+        let previous = match &self.story_cache {
+            Some(story_cache) => story_cache.lock().await.peek(&id).map(CachedStory::to_story),
+            None => None,
+        };
+
+        let story = story_from_item(self.get_item(id).await?)?;
+        if let Some(story_cache) = &self.story_cache {
+            story_cache.lock().await.put(id, CachedStory::from(&story));
+        }
+        Ok((previous, story))
     }
 
     // Get details for multiple stories in parallel, processing in chunks with caching
     pub async fn get_stories_details(&self, ids: Vec<HackerNewsID>, chunk_size: Option<usize>) -> Result<Vec<HackerNewsStory>> {
         let chunk_size = chunk_size.unwrap_or(5);
+        let ids = dedup_ids(ids);
         debug!("Fetching {} stories with chunk size {}", ids.len(), chunk_size);
         
         let mut all_stories = Vec::with_capacity(ids.len());
         let mut ids_to_fetch = Vec::new();
         
         // First check which stories are already in cache
-        {
-            let mut cache = self.story_cache.lock().await;
+        if let Some(story_cache) = &self.story_cache {
+            let mut cache = story_cache.lock().await;
             for id in &ids {
                 if let Some(cached_story) = cache.get(id) {
                     debug!("Cache hit for story ID: {}", *id);
-                    if let Ok(story) = cached_story.to_story() {
-                        all_stories.push(story);
-                        continue;
-                    }
-                    // If there's an error converting the cached story, we'll fetch it again
-                    debug!("Error converting cached story ID: {}, will re-fetch", *id);
+                    metrics().record_cache_hit();
+                    all_stories.push(cached_story.to_story());
+                    continue;
                 }
                 ids_to_fetch.push(*id);
             }
+        } else {
+            ids_to_fetch.extend(&ids);
         }
         
         if ids_to_fetch.is_empty() {
@@ -240,10 +410,13 @@ impl HnClient {
                 let client = self.clone();
                 
                 // Spawn a task for each story (now using our get_story_details method which includes caching)
-                let task = tokio::spawn(async move {
-                    info!("Fetching story ID: {}", id);
-                    client.get_story_details(id).await
-                });
+                let task = tokio::spawn(
+                    async move {
+                        info!("Fetching story ID: {}", id);
+                        client.get_story_details(id).await
+                    }
+                    .in_current_span(),
+                );
                 
                 tasks.push(task);
             }
@@ -270,8 +443,787 @@ impl HnClient {
         Ok(all_stories)
     }
 
+    /// Whether story details are cached (false with `with_cache_disabled` / `--no-cache`)
+    pub fn is_cache_enabled(&self) -> bool {
+        self.story_cache.is_some()
+    }
+
+    /// Write the cached stories to `path` as a JSON array, least recently used first so
+    /// `load_cache_file` restores the same order. Returns the number of stories written.
+    pub async fn save_cache_file(&self, path: &Path) -> Result<usize> {
+        let Some(story_cache) = &self.story_cache else {
+            return Err(anyhow!("The story cache is disabled"));
+        };
+        let stories = story_cache
+            .lock()
+            .await
+            .iter()
+            .rev()
+            .map(|(_, story)| story.clone())
+            .collect::<Vec<_>>();
+        let json = serde_json::to_vec(&stories)?;
+
+        // Write a sibling file first so an interrupted save leaves the old file intact
+        let temp_path = path.with_extension("tmp");
+        tokio::fs::write(&temp_path, json)
+            .await
+            .map_err(|e| anyhow!("Failed to write cache file {}: {}", temp_path.display(), e))?;
+        tokio::fs::rename(&temp_path, path)
+            .await
+            .map_err(|e| anyhow!("Failed to write cache file {}: {}", path.display(), e))?;
+        info!("Saved {} cached stories to {}", stories.len(), path.display());
+        Ok(stories.len())
+    }
+
+    /// Load stories written by `save_cache_file` into the cache. Returns the number of
+    /// stories loaded; only the most recent ones are kept if the file holds more than
+    /// the cache capacity.
+    pub async fn load_cache_file(&self, path: &Path) -> Result<usize> {
+        let Some(story_cache) = &self.story_cache else {
+            return Err(anyhow!("The story cache is disabled"));
+        };
+        let json = tokio::fs::read(path)
+            .await
+            .map_err(|e| anyhow!("Failed to read cache file {}: {}", path.display(), e))?;
+        let stories: Vec<CachedStory> = serde_json::from_slice(&json)
+            .map_err(|e| anyhow!("Failed to parse cache file {}: {}", path.display(), e))?;
+
+        let loaded = stories.len();
+        let mut cache = story_cache.lock().await;
+        for story in stories {
+            cache.put(story.id, story);
+        }
+        info!("Loaded {} stories from {}", loaded, path.display());
+        Ok(loaded)
+    }
+
+    /// Warm the story cache for `ids` in a background task, e.g. the next page of a feed.
+    /// Returns `None` (and fetches nothing) when the cache is disabled.
+    pub fn spawn_prefetch(&self, ids: Vec<HackerNewsID>, chunk_size: Option<usize>) -> Option<JoinHandle<()>> {
+        if !self.is_cache_enabled() || ids.is_empty() {
+            return None;
+        }
+        let client = self.clone();
+        Some(tokio::spawn(
+            async move {
+                let requested = ids.len();
+                match client.get_stories_details(ids, chunk_size).await {
+                    Ok(stories) => debug!("Prefetched {} of {} stories", stories.len(), requested),
+                    Err(e) => warn!("Error prefetching stories: {}", e),
+                }
+            }
+            .in_current_span(),
+        ))
+    }
+
+    // Get any item (story, comment, poll, ...) by ID from the Firebase API
+    pub async fn get_item(&self, id: HackerNewsID) -> Result<HnItem> {
+        let _permit = self.acquire_request_permit().await?;
+        let item: Option<HnItem> = self
+            .http
+            .get(format!("{}/item/{}.json", self.api_base_url, id))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch item with ID {}: {}", id, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to fetch item with ID {}: {}", id, e))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse item with ID {}: {}", id, e))?;
+
+        // The API answers `null` for IDs that don't exist
+        item.ok_or_else(|| anyhow!("Item with ID {} does not exist", id))
+    }
+
+    // Get a user profile from the Firebase API; `None` if the user does not exist
+    pub async fn get_user(&self, username: &str) -> Result<Option<HnUser>> {
+        let _permit = self.acquire_request_permit().await?;
+        self.http
+            .get(format!("{}/user/{}.json", self.api_base_url, username))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch user {}: {}", username, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to fetch user {}: {}", username, e))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse user {}: {}", username, e))
+    }
+
+    // Fetch several user profiles, `chunk_size` at a time. Each username gets its own
+    // result so one missing or failing user does not fail the others.
+    pub async fn get_users(&self, usernames: &[String], chunk_size: usize) -> Vec<(String, Result<Option<HnUser>>)> {
+        let mut results = Vec::with_capacity(usernames.len());
+        for chunk in usernames.chunks(chunk_size.max(1)) {
+            let users = futures::future::join_all(chunk.iter().map(|username| self.get_user(username))).await;
+            results.extend(chunk.iter().cloned().zip(users));
+        }
+        debug!("Fetched {} user profiles", results.len());
+        results
+    }
+
+    // Walk the `parent` links of `item` up to the root story. Returns the ancestors root
+    // first, and a note when the walk stopped early (missing parent or depth cap), so a
+    // broken chain still yields the part that could be fetched.
+    pub async fn get_ancestors(&self, item: &HnItem, max_depth: usize) -> (Vec<HnItem>, Option<String>) {
+        let mut ancestors = Vec::new();
+        let mut parent = item.parent;
+        while let Some(parent_id) = parent {
+            if ancestors.len() >= max_depth {
+                return (ancestors, Some(format!("stopped after {} parents", max_depth)));
+            }
+            match self.get_item(parent_id).await {
+                Ok(parent_item) => {
+                    parent = parent_item.parent;
+                    ancestors.insert(0, parent_item);
+                }
+                Err(e) => {
+                    warn!("Error fetching parent of item {}: {}", item.id, e);
+                    return (ancestors, Some(format!("could not fetch item {}", parent_id)));
+                }
+            }
+        }
+        debug!("Found {} ancestors of item {}", ancestors.len(), item.id);
+        (ancestors, None)
+    }
+
+    // Fetch a poll and all of its options. Every option is needed for correct totals,
+    // so a failed option fetch fails the whole call.
+    pub async fn get_poll_with_options(&self, id: HackerNewsID) -> Result<(HnItem, Vec<HnItem>)> {
+        let poll = self.get_item(id).await?;
+        if poll.item_type.as_deref() != Some("poll") {
+            return Err(anyhow!(
+                "Item with ID {} is a {}, not a poll",
+                id,
+                poll.item_type.as_deref().unwrap_or("item of unknown type")
+            ));
+        }
+        let options = futures::future::try_join_all(poll.parts.iter().map(|part| self.get_item(*part))).await?;
+        debug!("Fetched {} options for poll {}", options.len(), id);
+        Ok((poll, options))
+    }
+
+    // Fetch an item exactly as the API returns it, bypassing the cache (for debugging)
+    pub async fn get_raw_item(&self, id: HackerNewsID) -> Result<serde_json::Value> {
+        let _permit = self.acquire_request_permit().await?;
+        let item: serde_json::Value = self
+            .http
+            .get(format!("{}/item/{}.json", self.api_base_url, id))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch item with ID {}: {}", id, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to fetch item with ID {}: {}", id, e))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse item with ID {}: {}", id, e))?;
+
+        if item.is_null() {
+            return Err(anyhow!("Item with ID {} does not exist", id));
+        }
+        Ok(item)
+    }
+
+    // Get the first `limit` visible top-level comments of a story.
+    // HN returns a story's children in its own ranking order, so position is the best
+    // available proxy for comment quality (comment scores are not exposed by the API).
+    pub async fn get_top_comments(&self, story: &HackerNewsStory, limit: usize) -> Result<Vec<HnItem>> {
+        if limit > 0 && story.number_of_comments >= LARGE_THREAD_COMMENTS {
+            match self.get_top_comments_from_algolia(story, limit).await {
+                Ok(comments) => return Ok(comments),
+                Err(e) => error!("Falling back to per-comment requests: {}", e),
+            }
+        }
+        self.get_top_comments_from_items(story, limit).await
+    }
+
+    // Distinct authors of a story's visible top-level comments with how many each wrote, in
+    // order of their first comment. Always a single Algolia request, whatever the thread size,
+    // since only the names are needed; there is no per-comment fallback.
+    pub async fn get_commenters(&self, story: &HackerNewsStory) -> Result<Vec<(String, usize)>> {
+        if story.comments.is_empty() {
+            return Ok(Vec::new());
+        }
+        let comments = self.get_top_comments_from_algolia(story, story.comments.len()).await?;
+        let mut commenters: Vec<(String, usize)> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for author in comments.into_iter().filter_map(|comment| comment.by) {
+            match positions.get(&author) {
+                Some(&position) => commenters[position].1 += 1,
+                None => {
+                    positions.insert(author.clone(), commenters.len());
+                    commenters.push((author, 1));
+                }
+            }
+        }
+        Ok(commenters)
+    }
+
+    // Load the whole comment tree in one request. Algolia does not keep HN's ranking,
+    // so the top-level comments are put back in the story's `kids` order.
+    async fn get_top_comments_from_algolia(&self, story: &HackerNewsStory, limit: usize) -> Result<Vec<HnItem>> {
+        let _permit = self.acquire_request_permit().await?;
+        let item: AlgoliaItem = self
+            .http
+            .get(format!("{}/items/{}", self.algolia_base_url, story.id))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch comment tree of story {}: {}", story.id, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to fetch comment tree of story {}: {}", story.id, e))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse comment tree of story {}: {}", story.id, e))?;
+        debug!("Algolia returned {} top-level comments for story {}", item.children.len(), story.id);
+
+        let mut children = item
+            .children
+            .into_iter()
+            .map(|child| (child.id, child))
+            .collect::<HashMap<_, _>>();
+        let comments = story
+            .comments
+            .iter()
+            .filter_map(|id| children.remove(id))
+            .filter(AlgoliaItem::is_visible)
+            .take(limit)
+            .map(|child| child.to_hn_item())
+            .collect();
+        Ok(comments)
+    }
+
+    // Fetch top-level comments one Firebase item at a time
+    async fn get_top_comments_from_items(&self, story: &HackerNewsStory, limit: usize) -> Result<Vec<HnItem>> {
+        let mut comments = Vec::with_capacity(limit);
+        if limit == 0 {
+            return Ok(comments);
+        }
+
+        // Fetch in windows so deleted or dead comments are replaced by the next ones
+        for window in story.comments.chunks(limit) {
+            let results = futures::future::join_all(window.iter().map(|id| self.get_item(*id))).await;
+            for result in results {
+                match result {
+                    Ok(comment) if comment.is_visible() => comments.push(comment),
+                    Ok(comment) => debug!("Skipping deleted or dead comment ID: {}", comment.id),
+                    Err(e) => error!("Error fetching comment: {}", e),
+                }
+            }
+
+            if comments.len() >= limit {
+                comments.truncate(limit);
+                break;
+            }
+        }
+
+        Ok(comments)
+    }
+
+    // Start of the readable text of the page a story links to. Only http(s) HTML pages are
+    // read, within `ARTICLE_TIMEOUT` and the first `MAX_ARTICLE_BYTES`; these requests go to
+    // arbitrary sites, so they do not count against the HN request limits. Story URLs are
+    // user-submitted, so hosts and redirect targets that resolve to loopback, private or
+    // link-local addresses are refused.
+    pub async fn fetch_article_excerpt(&self, url: &str) -> Result<String> {
+        let parsed = Url::parse(url).map_err(|e| anyhow!("Invalid article URL {}: {}", url, e))?;
+        article::check_article_url(&parsed, self.allow_private_article_hosts)?;
+
+        let mut response = self
+            .article_http
+            .get(parsed)
+            .timeout(article::ARTICLE_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch article {}: {}", url, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to fetch article {}: {}", url, e))?;
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if !article::is_html_content_type(&content_type) {
+            let content_type = if content_type.is_empty() { "unknown" } else { content_type.as_str() };
+            return Err(anyhow!("Article is not an HTML page (content type {})", content_type));
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| anyhow!("Failed to read article {}: {}", url, e))?
+        {
+            body.extend_from_slice(&chunk);
+            if body.len() >= article::MAX_ARTICLE_BYTES {
+                body.truncate(article::MAX_ARTICLE_BYTES);
+                break;
+            }
+        }
+        debug!("Read {} bytes of article {}", body.len(), url);
+
+        let text = article::extract_article_text(&String::from_utf8_lossy(&body));
+        if text.is_empty() {
+            return Err(anyhow!("No readable text found in article {}", url));
+        }
+        let excerpt = truncate_chars(&text, article::ARTICLE_EXCERPT_CHARS);
+        if excerpt.len() < text.len() {
+            Ok(format!("{}...", excerpt.trim_end()))
+        } else {
+            Ok(text)
+        }
+    }
+
+    // Find the HN submission of an external URL using Algolia search
+    pub async fn find_story_id_by_url(&self, url: &str) -> Result<Option<HackerNewsID>> {
+        let _permit = self.acquire_request_permit().await?;
+        let response: AlgoliaSearchResponse = self
+            .http
+            .get(format!("{}/search", self.algolia_base_url))
+            .query(&[
+                ("query", url),
+                ("tags", "story"),
+                ("restrictSearchableAttributes", "url"),
+            ])
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to search stories by URL {}: {}", url, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to search stories by URL {}: {}", url, e))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse search results for URL {}: {}", url, e))?;
+        debug!("Algolia returned {} hits for URL {}", response.hits.len(), url);
+
+        // Algolia matches URLs fuzzily, so only accept hits pointing at the same page.
+        // If the URL was submitted several times, prefer the highest-scored submission.
+        let wanted = normalize_url(url);
+        let story_id = response
+            .hits
+            .iter()
+            .filter(|hit| hit.url.as_deref().is_some_and(|hit_url| normalize_url(hit_url) == wanted))
+            .max_by_key(|hit| hit.points.unwrap_or(0))
+            .and_then(|hit| hit.item_id());
+
+        Ok(story_id)
+    }
+
+    // Stories currently on the front page, in front-page order. Algolia knows which
+    // stories are on the front page but returns them by relevance, so we fetch all of
+    // them, re-rank them with the gravity formula HN uses for the website and only then
+    // apply `limit`.
+    pub async fn get_front_page_stories(&self, limit: Option<usize>) -> Result<Vec<HackerNewsID>> {
+        let _permit = self.acquire_request_permit().await?;
+        let hits_per_page = FRONT_PAGE_HITS.to_string();
+        let response: AlgoliaSearchResponse = self
+            .http
+            .get(format!("{}/search", self.algolia_base_url))
+            .query(&[("tags", "front_page"), ("hitsPerPage", hits_per_page.as_str())])
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch front page stories: {}", e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to fetch front page stories: {}", e))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse front page stories: {}", e))?;
+        debug!("Algolia returned {} front page stories", response.hits.len());
+
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let mut ranked = response
+            .hits
+            .iter()
+            .filter_map(|hit| {
+                let age_hours = (now - hit.created_at_i?).max(0) as f64 / 3600.0;
+                Some((hit.item_id()?, front_page_rank(hit.points.unwrap_or(0), age_hours)))
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut ids = ranked.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+        if let Some(limit) = limit {
+            ids.truncate(limit);
+        }
+        info!("Retrieved {} front page stories", ids.len());
+        Ok(ids)
+    }
+
+    // Story IDs matching an Algolia tag filter (e.g. `ask_hn`, `show_hn`, `author_pg`),
+    // filtered server-side by points and submission time, in relevance order
+    pub async fn query_story_ids(
+        &self,
+        tags: &str,
+        min_points: Option<u32>,
+        created_after: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<HackerNewsID>> {
+        let _permit = self.acquire_request_permit().await?;
+        let hits_per_page = limit.to_string();
+        let numeric_filters = min_points
+            .map(|points| format!("points>={}", points))
+            .into_iter()
+            .chain(created_after.map(|time| format!("created_at_i>{}", time)))
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut query = vec![("tags", tags), ("hitsPerPage", hits_per_page.as_str())];
+        if !numeric_filters.is_empty() {
+            query.push(("numericFilters", numeric_filters.as_str()));
+        }
+
+        let response: AlgoliaSearchResponse = self
+            .http
+            .get(format!("{}/search", self.algolia_base_url))
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to query stories tagged {:?}: {}", tags, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to query stories tagged {:?}: {}", tags, e))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse stories tagged {:?}: {}", tags, e))?;
+        info!(
+            "Story query for tags {:?} ({}) returned {} of {} hits",
+            tags,
+            numeric_filters,
+            response.hits.len(),
+            response.nb_hits
+        );
+        Ok(response.hits.iter().filter_map(AlgoliaHit::item_id).collect())
+    }
+
+    // Full-text search over comments using Algolia, most relevant or newest first
+    pub async fn search_comments(&self, query: &str, limit: usize, sort: SearchSort) -> Result<Vec<AlgoliaHit>> {
+        Ok(self.search_comments_with_facets(query, limit, sort, &[]).await?.hits)
+    }
+
+    // Like `search_comments`, also asking Algolia to count all matching comments by `facets`
+    pub async fn search_comments_with_facets(
+        &self,
+        query: &str,
+        limit: usize,
+        sort: SearchSort,
+        facets: &[SearchFacet],
+    ) -> Result<AlgoliaSearchResponse> {
+        let _permit = self.acquire_request_permit().await?;
+        let hits_per_page = limit.to_string();
+        let mut params = vec![
+            ("query", query.to_string()),
+            ("tags", "comment".to_string()),
+            ("hitsPerPage", hits_per_page),
+        ];
+        if !facets.is_empty() {
+            let attributes = facets.iter().map(|facet| facet.attribute()).collect::<Vec<_>>();
+            params.push(("facets", attributes.join(",")));
+        }
+        let response: AlgoliaSearchResponse = self
+            .http
+            .get(format!("{}/{}", self.algolia_base_url, sort.endpoint()))
+            .query(&params)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to search comments for {:?}: {}", query, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to search comments for {:?}: {}", query, e))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse comment search results for {:?}: {}", query, e))?;
+        info!(
+            "Comment search for {:?} returned {} of {} hits",
+            query,
+            response.hits.len(),
+            response.nb_hits
+        );
+        Ok(response)
+    }
+
+    // An author's most recent submissions matching `tags` (e.g. `story`, `comment` or
+    // `(story,comment)`), newest first
+    pub async fn get_submissions_by_author(&self, username: &str, tags: &str, limit: usize) -> Result<Vec<AlgoliaHit>> {
+        let _permit = self.acquire_request_permit().await?;
+        let tags = format!("author_{},{}", username, tags);
+        let hits_per_page = limit.to_string();
+        let response: AlgoliaSearchResponse = self
+            .http
+            .get(format!("{}/search_by_date", self.algolia_base_url))
+            .query(&[("tags", tags.as_str()), ("hitsPerPage", hits_per_page.as_str())])
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch submissions of {}: {}", username, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Failed to fetch submissions of {}: {}", username, e))?
+            .json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse submissions of {}: {}", username, e))?;
+        info!(
+            "Submission search for {} ({}) returned {} of {} hits",
+            username,
+            tags,
+            response.hits.len(),
+            response.nb_hits
+        );
+        Ok(response.hits)
+    }
+
+    // Format a story or comment search hit, stories like `format_story`
+    pub fn format_submission_hit(hit: &AlgoliaHit) -> String {
+        match hit.to_story() {
+            Some(story) => Self::format_story(&story),
+            None => Self::format_comment_hit(hit),
+        }
+    }
+
+    // `Commenters (3): alice (2), bob, carol`; counts are shown for authors of several comments
+    pub fn format_commenters(commenters: &[(String, usize)]) -> String {
+        if commenters.is_empty() {
+            return "Commenters: [none]\n".to_string();
+        }
+        let names = commenters
+            .iter()
+            .map(|(name, count)| if *count > 1 { format!("{} ({})", name, count) } else { name.clone() })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("Commenters ({}): {}\n", commenters.len(), names)
+    }
+
+    // One `Top authors: a (12), b (7)` line per requested facet, most frequent values first
+    pub fn format_facets(facets: &HashMap<String, HashMap<String, u64>>, requested: &[SearchFacet]) -> String {
+        let mut summary = String::new();
+        for facet in requested {
+            let mut counts = facets
+                .get(facet.attribute())
+                .map(|counts| counts.iter().collect::<Vec<_>>())
+                .unwrap_or_default();
+            counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            let values = if counts.is_empty() {
+                "[none]".to_string()
+            } else {
+                counts
+                    .iter()
+                    .take(MAX_FACET_VALUES)
+                    .map(|(value, count)| format!("{} ({})", value, count))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            summary.push_str(&format!("{}: {}\n", facet.label(), values));
+        }
+        summary
+    }
+
+    // Format a comment search hit into a readable string
+    pub fn format_comment_hit(hit: &AlgoliaHit) -> String {
+        let date_time = hit
+            .created_at_i
+            .and_then(|time| OffsetDateTime::from_unix_timestamp(time).ok())
+            .map(|created_at| format!("{}", to_display_time(created_at)))
+            .unwrap_or_default();
+
+        format!(
+            "Story: {}{}\nBy: {}\nDate: {}\nLink: {}\nText: {}\n",
+            hit.story_title.as_deref().map(html_to_text).as_deref().unwrap_or("[unknown]"),
+            hit.story_id.map(|id| format!(" (ID: {})", id)).unwrap_or_default(),
+            hit.author.as_deref().unwrap_or("[unknown]"),
+            date_time,
+            item_permalink(&hit.object_id),
+            html_to_text(hit.comment_text.as_deref().unwrap_or_default())
+        )
+    }
+
+    // Format a comment into a readable string
+    pub fn format_comment(comment: &HnItem) -> String {
+        let date_time = comment
+            .time
+            .and_then(|time| OffsetDateTime::from_unix_timestamp(time).ok())
+            .map(|created_at| format!("{}", to_display_time(created_at)))
+            .unwrap_or_default();
+
+        format!(
+            "By: {}\nDate: {}\nID: {}\nText: {}\n",
+            comment.by.as_deref().unwrap_or("[unknown]"),
+            date_time,
+            comment.id,
+            html_to_text(comment.text.as_deref().unwrap_or_default())
+        )
+    }
+
+    // Format any item: stories like `format_story`, other types (comment, job, poll,
+    // pollopt) with the fields they have
+    pub fn format_item(item: &HnItem) -> String {
+        if item.item_type.as_deref() == Some("story") {
+            if let Ok(story) = story_from_item(item.clone()) {
+                return Self::format_story(&story);
+            }
+        }
+        let date_time = item
+            .time
+            .and_then(|time| OffsetDateTime::from_unix_timestamp(time).ok())
+            .map(|created_at| format!("{}", to_display_time(created_at)))
+            .unwrap_or_default();
+
+        let mut output = format!("Type: {}\n", item.item_type.as_deref().unwrap_or("unknown"));
+        if let Some(title) = &item.title {
+            output.push_str(&format!("Title: {}\n", html_to_text(title)));
+        }
+        if let Some(url) = item.url.as_deref().filter(|url| !url.is_empty()) {
+            output.push_str(&format!("URL: {}\n", url));
+        }
+        if let Some(text) = &item.text {
+            output.push_str(&format!("Text: {}\n", html_to_text(text)));
+        }
+        output.push_str(&format!("By: {}\nDate: {}\n", item.by.as_deref().unwrap_or("[unknown]"), date_time));
+        if let Some(score) = item.score {
+            output.push_str(&format!("Score: {}\n", score));
+        }
+        if let Some(parent) = item.parent {
+            output.push_str(&format!("Parent: {}\n", parent));
+        }
+        output.push_str(&format!("ID: {}\nHN: {}\n", item.id, item_permalink(item.id)));
+        output
+    }
+
+    // Format the path from the root story down to `item`, one numbered line per level.
+    // `note` explains a chain that could not be followed to the root.
+    pub fn format_thread_path(ancestors: &[HnItem], item: &HnItem, note: Option<&str>) -> String {
+        let mut output = String::from("Thread (root first):\n");
+        let mut level = 1;
+        if let Some(note) = note {
+            output.push_str(&format!("{}. [{}]\n", level, note));
+            level += 1;
+        }
+        for ancestor in ancestors {
+            let entry = match &ancestor.title {
+                Some(title) => format!("{}: {}", capitalize(ancestor.item_type.as_deref().unwrap_or("item")), html_to_text(title)),
+                None => {
+                    let author = ancestor.by.as_deref().unwrap_or("[deleted]");
+                    // One line per level, so paragraphs are joined
+                    let text = html_to_text(ancestor.text.as_deref().unwrap_or_default())
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let snippet = truncate_chars(&text, BREADCRUMB_TEXT_CHARS);
+                    match (snippet.is_empty(), snippet.len() < text.len()) {
+                        (true, _) => author.to_string(),
+                        (false, true) => format!("{}: {}…", author, snippet),
+                        (false, false) => format!("{}: {}", author, snippet),
+                    }
+                }
+            };
+            output.push_str(&format!("{}. {} (ID: {})\n", level, entry, ancestor.id));
+            level += 1;
+        }
+        output.push_str(&format!("{}. This {} (ID: {})\n", level, item.item_type.as_deref().unwrap_or("item"), item.id));
+        output
+    }
+
+    // Format a comment as a numbered entry with only its author, permalink and text
+    pub fn format_best_comment(rank: usize, comment: &HnItem) -> String {
+        format!(
+            "{}. {} ({})\n{}\n",
+            rank,
+            comment.by.as_deref().unwrap_or("[unknown]"),
+            item_permalink(comment.id),
+            html_to_text(comment.text.as_deref().unwrap_or_default())
+        )
+    }
+
+    // Format poll results with each option's votes and share of the total, most votes first
+    pub fn format_poll_results(poll: &HnItem, options: &[HnItem]) -> String {
+        let total: u64 = options.iter().map(|option| u64::from(option.score.unwrap_or(0))).sum();
+        let mut options = options.iter().collect::<Vec<_>>();
+        options.sort_by_key(|option| std::cmp::Reverse(option.score.unwrap_or(0)));
+
+        let mut output = format!(
+            "Poll: {} (ID: {})\nTotal votes: {}\n\n",
+            html_to_text(poll.title.as_deref().unwrap_or("[untitled]")),
+            poll.id,
+            total
+        );
+        if options.is_empty() {
+            output.push_str("No options found\n");
+        }
+        for (index, option) in options.iter().enumerate() {
+            let votes = option.score.unwrap_or(0);
+            // A poll nobody voted on yet shows 0% everywhere
+            let percentage = if total == 0 { 0.0 } else { f64::from(votes) * 100.0 / total as f64 };
+            output.push_str(&format!(
+                "{}. {} - {} {} ({:.1}%)\n",
+                index + 1,
+                html_to_text(option.text.as_deref().unwrap_or_default()),
+                votes,
+                if votes == 1 { "vote" } else { "votes" },
+                percentage
+            ));
+        }
+        output
+    }
+
+    // Text histogram of stories by comment count: one line per bucket with a bar of
+    // one '#' per story and the count
+    pub fn format_comment_histogram(stories: &[HackerNewsStory]) -> String {
+        const BUCKETS: [(&str, u32, u32); 4] = [("0-10", 0, 10), ("11-50", 11, 50), ("51-200", 51, 200), ("201+", 201, u32::MAX)];
+
+        let mut output = String::new();
+        for (label, min, max) in BUCKETS {
+            let count = stories
+                .iter()
+                .filter(|story| (min..=max).contains(&story.number_of_comments))
+                .count();
+            if count == 0 {
+                output.push_str(&format!("{:>6} | 0\n", label));
+            } else {
+                output.push_str(&format!("{:>6} | {} {}\n", label, "#".repeat(count), count));
+            }
+        }
+        output
+    }
+
+    // Format `username: karma` lines, highest karma first, followed by notes for
+    // usernames that do not exist or could not be fetched
+    pub fn format_karma_table(users: &[HnUser], not_found: &[String], failed: &[String]) -> String {
+        let mut users = users.iter().collect::<Vec<_>>();
+        users.sort_by(|a, b| b.karma.cmp(&a.karma).then_with(|| a.id.cmp(&b.id)));
+
+        let mut output = String::new();
+        for user in &users {
+            output.push_str(&format!("{}: {}\n", user.id, user.karma));
+        }
+        if users.is_empty() {
+            output.push_str("No users found\n");
+        }
+        if !not_found.is_empty() {
+            output.push_str(&format!("\n(not found: {})\n", not_found.join(", ")));
+        }
+        if !failed.is_empty() {
+            output.push_str(&format!("\n(could not be fetched: {})\n", failed.join(", ")));
+        }
+        output
+    }
+
     // Format a story into a readable string
     pub fn format_story(story: &HackerNewsStory) -> String {
+        Self::format_story_with(story, &FormatOptions::default())
+    }
+
+    // Format a story as labeled lines, or with `field_labels` off as exactly nine value lines
+    pub fn format_story_with(story: &HackerNewsStory, options: &FormatOptions) -> String {
+        if !options.field_labels {
+            // Keep every value on one line so positions stay fixed
+            let text = html_to_text(&story.text).split_whitespace().collect::<Vec<_>>().join(" ");
+            return format!(
+                "{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n",
+                html_to_text(&story.title),
+                story.url,
+                text,
+                story.by,
+                story.score,
+                to_display_time(story.created_at),
+                story.number_of_comments,
+                story.id,
+                item_permalink(story.id)
+            );
+        }
+
         // Display URL if it's not empty
         let url_section = if !story.url.is_empty() {
             format!("URL: {}\n", story.url)
@@ -281,25 +1233,100 @@ impl HnClient {
 
         // Display text if it's not empty
         let text_section = if !story.text.is_empty() {
-            format!("Text: {}\n", story.text)
+            format!("Text: {}\n", html_to_text(&story.text))
         } else {
             String::new()
         };
 
         // Format created_at to string
-        let created_at = &story.created_at;
-        let date_time = format!("{}", created_at);
+        let date_time = format!("{}", to_display_time(story.created_at));
 
         format!(
-            "Title: {}\n{}{}By: {}\nScore: {}\nDate: {}\nComments: {}\nID: {}\n",
-            story.title,
+            "Title: {}\n{}{}By: {}\nScore: {}\nDate: {}\nComments: {}\nID: {}\nHN: {}\n",
+            html_to_text(&story.title),
             url_section,
             text_section,
             story.by,
             story.score,
             date_time,
             story.number_of_comments,
-            story.id
+            story.id,
+            item_permalink(story.id)
         )
     }
-}
\ No newline at end of file
+}
+
+// HN's published ranking: votes decay with age so newer stories can outrank older,
+// higher-scored ones. Ignores the penalties HN applies for flags and moderation.
+fn front_page_rank(points: u32, age_hours: f64) -> f64 {
+    (points.saturating_sub(1) as f64) / (age_hours + 2.0).powf(1.8)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn build_http_client(user_agent: &str) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(user_agent)
+        .build()
+        .map_err(|e| anyhow!("Failed to build HTTP client with user agent {:?}: {}", user_agent, e))
+}
+
+/// Link to an item's discussion page on news.ycombinator.com
+pub fn item_permalink(id: impl std::fmt::Display) -> String {
+    format!("https://news.ycombinator.com/item?id={}", id)
+}
+
+// Remove duplicate IDs while keeping the first occurrence of each
+fn dedup_ids(ids: Vec<HackerNewsID>) -> Vec<HackerNewsID> {
+    let mut seen = HashSet::with_capacity(ids.len());
+    ids.into_iter().filter(|id| seen.insert(*id)).collect()
+}
+
+/// Extract the item ID from a Hacker News permalink such as
+/// `https://news.ycombinator.com/item?id=39617316`
+pub fn parse_item_id_from_url(url: &str) -> Option<HackerNewsID> {
+    let parsed = parse_url(url)?;
+    let host = parsed.host_str()?;
+    if host != "news.ycombinator.com" || parsed.path() != "/item" {
+        return None;
+    }
+
+    parsed
+        .query_pairs()
+        .find(|(key, _)| key == "id")
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+// Parse a URL, assuming https when the scheme is missing
+fn parse_url(url: &str) -> Option<Url> {
+    let url = url.trim();
+    Url::parse(url)
+        .ok()
+        .filter(|parsed| parsed.has_host())
+        .or_else(|| Url::parse(&format!("https://{}", url)).ok())
+}
+
+/// Host of a story URL without a leading "www.", e.g. "github.com"
+pub fn story_domain(url: &str) -> Option<String> {
+    let host = parse_url(url)?.host_str()?.to_lowercase();
+    Some(host.strip_prefix("www.").map(str::to_string).unwrap_or(host))
+}
+
+// Normalize a URL for comparison: ignore scheme, leading "www." and trailing slashes
+fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let without_www = without_scheme.strip_prefix("www.").unwrap_or(without_scheme);
+    without_www.trim_end_matches('/').to_lowercase()
+}
+
+// Strip trailing slashes so endpoint paths can be appended with a single `/`
+fn normalize_base_url(url: &str) -> Arc<str> {
+    Arc::from(url.trim().trim_end_matches('/'))
+}
@@ -0,0 +1,32 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+// Spaces requests evenly so that at most `requests_per_second` start in any second.
+// Callers reserve the next free slot under the lock and sleep outside it, so waiting
+// callers are served in the order they arrived.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / requests_per_second.max(1),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    // Wait until this caller may send its request
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
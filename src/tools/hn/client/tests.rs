@@ -1,4 +1,9 @@
+use super::*;
+use std::time::{Duration, Instant};
+
 #[cfg(test)]
+// The live-API tests predate the shared imports above and keep their own module
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::tools::hn::client::HnClient;
     use std::time::Instant;
@@ -107,4 +112,835 @@ mod tests {
         println!("  Ask stories count: {}", ask_stories.len());
         println!("  Show stories count: {}", show_stories.len());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_dedup_ids_preserves_order() {
+        use super::super::dedup_ids;
+
+        let ids = vec![3, 1, 3, 2, 1, 3];
+        assert_eq!(dedup_ids(ids), vec![3, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_get_stories_details_with_duplicates() {
+        let mut server = mockito::Server::new_async().await;
+        let mut items = Vec::new();
+        for id in [8863, 121003, 9224] {
+            let item = server
+                .mock("GET", format!("/item/{}.json", id).as_str())
+                .with_header("content-type", "application/json")
+                .with_body(format!(r#"{{"by":"pg","id":{},"score":1,"time":1175714200,"title":"Story {}","type":"story"}}"#, id, id))
+                .expect(1)
+                .create_async()
+                .await;
+            items.push(item);
+        }
+
+        // Each story is requested several times but must be fetched and returned once
+        let client = HnClient::with_cache_disabled().with_base_url(&server.url());
+        let duplicated_ids = vec![8863, 121003, 8863, 9224, 121003, 9224, 8863];
+        let stories = client.get_stories_details(duplicated_ids, Some(2)).await.unwrap();
+
+        let received_ids: Vec<u32> = stories.iter().map(|s| s.id).collect();
+        assert_eq!(received_ids, vec![8863, 121003, 9224]);
+        for item in items {
+            item.assert_async().await;
+        }
+    }
+}
+
+#[test]
+fn test_parse_item_id_from_url() {
+    assert_eq!(
+        parse_item_id_from_url("https://news.ycombinator.com/item?id=39617316"),
+        Some(39617316)
+    );
+    assert_eq!(
+        parse_item_id_from_url("news.ycombinator.com/item?id=8863"),
+        Some(8863)
+    );
+    assert_eq!(parse_item_id_from_url("https://news.ycombinator.com/news"), None);
+    assert_eq!(parse_item_id_from_url("https://example.com/item?id=1"), None);
+}
+
+#[test]
+fn test_story_domain() {
+    assert_eq!(story_domain("https://www.GitHub.com/rust-lang/rust").as_deref(), Some("github.com"));
+    assert_eq!(story_domain("http://blog.rust-lang.org/2025/05/02/").as_deref(), Some("blog.rust-lang.org"));
+    assert_eq!(story_domain("example.com/page").as_deref(), Some("example.com"));
+    assert_eq!(story_domain(""), None);
+}
+
+#[test]
+fn test_normalize_url() {
+    assert_eq!(
+        normalize_url("https://www.Example.com/path/"),
+        normalize_url("http://example.com/path")
+    );
+}
+
+#[test]
+fn test_front_page_rank_favors_newer_stories() {
+    // A fresh story with fewer points outranks an older, higher-scored one
+    assert!(front_page_rank(50, 1.0) > front_page_rank(200, 12.0));
+    // At the same age, more points rank higher
+    assert!(front_page_rank(100, 3.0) > front_page_rank(10, 3.0));
+    // The submitter's own vote does not count
+    assert_eq!(front_page_rank(1, 0.0), 0.0);
+}
+
+#[test]
+fn test_html_to_text() {
+    let html = "It&#x27;s &quot;fast&quot; &amp; 2 &gt; 1<p>See <a href=\"https:&#x2F;&#x2F;example.com&#x2F;a&#x2F;very&#x2F;long&#x2F;path\" rel=\"nofollow\">https:&#x2F;&#x2F;example.com&#x2F;a&#x2F;very&#x2F;lo...</a> and <a href=\"https:&#x2F;&#x2F;example.org\">https:&#x2F;&#x2F;example.org</a><p><i>Edit:</i> typo";
+
+    assert_eq!(
+        html_to_text(html),
+        "It's \"fast\" & 2 > 1\n\nSee https://example.com/a/very/lo... (https://example.com/a/very/long/path) and https://example.org\n\nEdit: typo"
+    );
+}
+
+#[test]
+fn test_html_to_text_leaves_plain_text_alone() {
+    assert_eq!(html_to_text("AT&T < 5 & co;"), "AT&T < 5 & co;");
+}
+
+#[test]
+fn test_format_comment_hit() {
+    let hit: AlgoliaHit = serde_json::from_str(
+        r#"{"objectID":"9224","author":"BrandonM","comment_text":"I have a few qualms with this app.","created_at_i":1175727559,"story_id":8863,"story_title":"My YC app: Dropbox - Throw away your USB drive","parent_id":8863}"#,
+    )
+    .unwrap();
+
+    let formatted = HnClient::format_comment_hit(&hit);
+    assert!(formatted.starts_with("Story: My YC app: Dropbox - Throw away your USB drive (ID: 8863)\n"));
+    assert!(formatted.contains("By: BrandonM\n"));
+    assert!(formatted.contains("Link: https://news.ycombinator.com/item?id=9224\n"));
+    assert!(formatted.contains("Text: I have a few qualms with this app.\n"));
+}
+
+#[tokio::test]
+async fn test_with_base_url_uses_mock_server() {
+    let mut server = mockito::Server::new_async().await;
+    let _feed = server
+        .mock("GET", "/topstories.json")
+        .with_header("content-type", "application/json")
+        .with_body("[8863, 8864, 8865]")
+        .create_async()
+        .await;
+    let _item = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"by":"dhouston","descendants":71,"id":8863,"kids":[9224,8917],"score":111,"time":1175714200,"title":"My YC app: Dropbox - Throw away your USB drive","type":"story","url":"http://www.getdropbox.com/u/2/screencast.html"}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_base_url(&format!("{}/", server.url()));
+
+    let ids = client.get_top_stories(Some(2)).await.unwrap();
+    assert_eq!(ids, vec![8863, 8864]);
+
+    let story = client.get_story_details(8863).await.unwrap();
+    assert_eq!(story.title, "My YC app: Dropbox - Throw away your USB drive");
+    assert_eq!(story.by, "dhouston");
+    assert_eq!(story.score, 111);
+    assert_eq!(story.number_of_comments, 71);
+    assert_eq!(story.comments, vec![9224, 8917]);
+    assert_eq!(story.created_at.unix_timestamp(), 1175714200);
+
+    // The second read is served from the cache, so the item endpoint is hit only once
+    let cached = client.get_story_details(8863).await.unwrap();
+    assert_eq!(cached.created_at, story.created_at);
+    _item.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_get_story_details_rejects_non_story_items() {
+    let mut server = mockito::Server::new_async().await;
+    let _item = server
+        .mock("GET", "/item/2921983.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"norvig","id":2921983,"parent":2921506,"text":"Aw shucks","time":1314211127,"type":"comment"}"#)
+        .create_async()
+        .await;
+    let _missing = server
+        .mock("GET", "/item/1.json")
+        .with_header("content-type", "application/json")
+        .with_body("null")
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_base_url(&server.url());
+
+    assert!(client.get_story_details(2921983).await.is_err());
+    assert!(client.get_story_details(1).await.is_err());
+}
+
+#[tokio::test]
+async fn test_cache_disabled_always_hits_api() {
+    let mut server = mockito::Server::new_async().await;
+    let item = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"dhouston","id":8863,"score":111,"time":1175714200,"title":"My YC app: Dropbox","type":"story"}"#)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let client = HnClient::with_cache_disabled().with_base_url(&server.url());
+    client.get_story_details(8863).await.unwrap();
+    client.get_story_details(8863).await.unwrap();
+    let stories = client.get_stories_details(vec![8863], Some(1)).await.unwrap();
+    assert_eq!(stories.len(), 1);
+
+    item.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_request_limit_is_shared_and_released() {
+    let mut server = mockito::Server::new_async().await;
+    let mut items = Vec::new();
+    for id in [8863, 8864, 8865] {
+        items.push(
+            server
+                .mock("GET", format!("/item/{}.json", id).as_str())
+                .with_header("content-type", "application/json")
+                .with_body(format!(r#"{{"by":"pg","id":{},"score":1,"time":1175714200,"title":"Story {}","type":"story"}}"#, id, id))
+                .create_async()
+                .await,
+        );
+    }
+
+    let client = HnClient::with_cache_disabled()
+        .with_base_url(&server.url())
+        .with_max_in_flight_requests(1);
+    let clone = client.clone();
+
+    // A chunk wider than the limit still completes; requests just queue for the permit
+    let stories = client.get_stories_details(vec![8863, 8864, 8865], Some(3)).await.unwrap();
+    assert_eq!(stories.len(), 3);
+    assert_eq!(clone.request_limiter.available_permits(), 1);
+    assert!(Arc::ptr_eq(&client.request_limiter, &clone.request_limiter));
+
+    for item in items {
+        item.assert_async().await;
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limit_spaces_requests() {
+    let mut server = mockito::Server::new_async().await;
+    let item = server
+        .mock("GET", mockito::Matcher::Regex(r"^/item/\d+\.json$".to_string()))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"pg","id":1,"score":57,"time":1160418111,"title":"Y Combinator","type":"story"}"#)
+        .expect(5)
+        .create_async()
+        .await;
+
+    // 20 requests per second: five concurrent requests need at least four 50ms gaps
+    let client = HnClient::with_cache_disabled()
+        .with_base_url(&server.url())
+        .with_max_requests_per_second(20);
+    let start = Instant::now();
+    let stories = client.get_stories_details(vec![1, 2, 3, 4, 5], Some(5)).await.unwrap();
+
+    assert_eq!(stories.len(), 5);
+    assert!(start.elapsed() >= Duration::from_millis(200), "took {:?}", start.elapsed());
+    item.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_rate_limit_covers_algolia_searches() {
+    let mut server = mockito::Server::new_async().await;
+    let search = server
+        .mock("GET", "/search_by_date")
+        .match_query(mockito::Matcher::Any)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"hits":[],"nbHits":0}"#)
+        .expect(5)
+        .create_async()
+        .await;
+
+    // Algolia searches share the limits of the Firebase requests
+    let client = HnClient::with_cache_disabled()
+        .with_algolia_base_url(&server.url())
+        .with_max_requests_per_second(20);
+    let start = Instant::now();
+    let results = futures::future::join_all((0..5).map(|_| client.search_comments("rust", 5, SearchSort::Date))).await;
+
+    assert!(results.iter().all(Result::is_ok));
+    assert!(start.elapsed() >= Duration::from_millis(200), "took {:?}", start.elapsed());
+    search.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_user_agent_header() {
+    let mut server = mockito::Server::new_async().await;
+    let default_agent = server
+        .mock("GET", "/topstories.json")
+        .match_header("user-agent", DEFAULT_USER_AGENT)
+        .with_body("[8863]")
+        .create_async()
+        .await;
+    let custom_agent = server
+        .mock("GET", "/newstories.json")
+        .match_header("user-agent", "my-deployment/1.0 (ops@example.com)")
+        .with_body("[8864]")
+        .create_async()
+        .await;
+
+    let client = HnClient::with_cache_disabled().with_base_url(&server.url());
+    assert_eq!(client.get_top_stories(None).await.unwrap(), vec![8863]);
+
+    let client = client.with_user_agent("my-deployment/1.0 (ops@example.com)").unwrap();
+    assert_eq!(client.get_latest_stories(None).await.unwrap(), vec![8864]);
+
+    default_agent.assert_async().await;
+    custom_agent.assert_async().await;
+    assert!(HnClient::new().with_user_agent("bad\nagent").is_err());
+}
+
+#[tokio::test]
+async fn test_get_commenters() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/item/1.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"pg","descendants":5,"id":1,"kids":[2,3,4,5],"score":57,"time":1160418111,"title":"Y Combinator","type":"story"}"#)
+        .create_async()
+        .await;
+    let tree = server
+        .mock("GET", "/items/1")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"id":1,"type":"story","author":"pg","text":null,"created_at_i":1160418111,"parent_id":null,"children":[
+                {"id":5,"type":"comment","author":"sama","text":"Third","created_at_i":1160418500,"parent_id":1,"children":[]},
+                {"id":4,"type":"comment","author":"jl","text":"Second","created_at_i":1160418400,"parent_id":1,"children":[]},
+                {"id":3,"type":"comment","author":null,"text":null,"created_at_i":1160418300,"parent_id":1,"children":[]},
+                {"id":2,"type":"comment","author":"sama","text":"First","created_at_i":1160418200,"parent_id":1,"children":[]}
+            ]}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    // Even a small thread is read from Algolia instead of one request per comment
+    let comments = server
+        .mock("GET", mockito::Matcher::Regex(r"^/item/[2-5]\.json$".to_string()))
+        .expect(0)
+        .create_async()
+        .await;
+
+    let client = HnClient::with_cache_disabled()
+        .with_base_url(&server.url())
+        .with_algolia_base_url(&server.url());
+    let story = client.get_story_details(1).await.unwrap();
+    let commenters = client.get_commenters(&story).await.unwrap();
+
+    assert_eq!(commenters, vec![("sama".to_string(), 2), ("jl".to_string(), 1)]);
+    assert_eq!(HnClient::format_commenters(&commenters), "Commenters (2): sama (2), jl\n");
+    assert_eq!(HnClient::format_commenters(&[]), "Commenters: [none]\n");
+    tree.assert_async().await;
+    comments.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_large_thread_comments_come_from_algolia() {
+    let mut server = mockito::Server::new_async().await;
+    let story = server
+        .mock("GET", "/item/1.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"pg","descendants":150,"id":1,"kids":[4,2,3],"score":57,"time":1160418111,"title":"Y Combinator","type":"story"}"#)
+        .create_async()
+        .await;
+    let tree = server
+        .mock("GET", "/items/1")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"id":1,"type":"story","author":"pg","text":null,"created_at_i":1160418111,"parent_id":null,"children":[
+                {"id":2,"type":"comment","author":"sama","text":"Second","created_at_i":1160418200,"parent_id":1,"children":[]},
+                {"id":3,"type":"comment","author":null,"text":null,"created_at_i":1160418300,"parent_id":1,"children":[]},
+                {"id":4,"type":"comment","author":"jl","text":"First","created_at_i":1160418400,"parent_id":1,"children":[{"id":5,"type":"comment","author":"pg","text":"Reply","created_at_i":1160418500,"parent_id":4,"children":[]}]}
+            ]}"#,
+        )
+        .create_async()
+        .await;
+    let comment = server.mock("GET", "/item/2.json").expect(0).create_async().await;
+
+    let client = HnClient::with_cache_disabled()
+        .with_base_url(&server.url())
+        .with_algolia_base_url(&server.url());
+    let story_details = client.get_story_details(1).await.unwrap();
+    let comments = client.get_top_comments(&story_details, 5).await.unwrap();
+
+    // HN's ranking order from `kids` is kept and the deleted comment is skipped
+    let ids = comments.iter().map(|comment| comment.id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![4, 2]);
+    assert_eq!(comments[0].by.as_deref(), Some("jl"));
+    assert_eq!(comments[0].kids, vec![5]);
+
+    story.assert_async().await;
+    tree.assert_async().await;
+    comment.assert_async().await;
+}
+
+#[test]
+fn test_format_best_comment() {
+    let comment: HnItem = serde_json::from_str(
+        r#"{"by":"BrandonM","id":9224,"parent":8863,"text":"I have a few qualms with this app:<p>1. For a Linux user, you can already build such a system yourself quite trivially.","time":1175727559,"type":"comment"}"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        HnClient::format_best_comment(1, &comment),
+        "1. BrandonM (https://news.ycombinator.com/item?id=9224)\nI have a few qualms with this app:\n\n1. For a Linux user, you can already build such a system yourself quite trivially.\n"
+    );
+}
+
+#[test]
+fn test_format_poll_results() {
+    let poll: HnItem = serde_json::from_str(
+        r#"{"by":"pg","id":126809,"parts":[126810,126811,126812],"score":46,"time":1204403652,"title":"Poll: What would happen if News.YC had explicit support for polls?","type":"poll"}"#,
+    )
+    .unwrap();
+    let option = |id: u32, score: u32, text: &str| {
+        serde_json::from_value::<HnItem>(serde_json::json!({"id": id, "type": "pollopt", "poll": 126809, "score": score, "text": text}))
+            .unwrap()
+    };
+    let options = [option(126810, 1, "Nothing"), option(126811, 3, "More polls &amp; fewer stories"), option(126812, 0, "Fewer")];
+
+    assert_eq!(
+        HnClient::format_poll_results(&poll, &options),
+        "Poll: Poll: What would happen if News.YC had explicit support for polls? (ID: 126809)\nTotal votes: 4\n\n\
+         1. More polls & fewer stories - 3 votes (75.0%)\n\
+         2. Nothing - 1 vote (25.0%)\n\
+         3. Fewer - 0 votes (0.0%)\n"
+    );
+
+    // No votes yet: no division by zero
+    let unvoted = [option(126810, 0, "Yes"), option(126811, 0, "No")];
+    let output = HnClient::format_poll_results(&poll, &unvoted);
+    assert!(output.contains("Total votes: 0\n"));
+    assert!(output.contains("1. Yes - 0 votes (0.0%)\n2. No - 0 votes (0.0%)\n"));
+}
+
+#[tokio::test]
+async fn test_query_story_ids_sends_filters() {
+    let mut server = mockito::Server::new_async().await;
+    let search = server
+        .mock("GET", "/search")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("tags".into(), "ask_hn".into()),
+            mockito::Matcher::UrlEncoded("hitsPerPage".into(), "5".into()),
+            mockito::Matcher::UrlEncoded("numericFilters".into(), "points>=200,created_at_i>1700000000".into()),
+        ]))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"hits":[{"objectID":"363","points":250},{"objectID":"121003","points":210}],"nbHits":2}"#)
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_algolia_base_url(&server.url());
+    let ids = client
+        .query_story_ids("ask_hn", Some(200), Some(1_700_000_000), 5)
+        .await
+        .unwrap();
+
+    assert_eq!(ids, vec![363, 121003]);
+    search.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_front_page_ranks_all_hits_before_limit() {
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    // Relevance order: an old high-scored story, a fresh one, then one in between
+    let body = format!(
+        r#"{{"hits":[{{"objectID":"1","points":300,"created_at_i":{}}},{{"objectID":"2","points":60,"created_at_i":{}}},{{"objectID":"3","points":100,"created_at_i":{}}}],"nbHits":3}}"#,
+        now - 20 * 3600,
+        now - 3600,
+        now - 3 * 3600
+    );
+    let mut server = mockito::Server::new_async().await;
+    let search = server
+        .mock("GET", "/search")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("tags".into(), "front_page".into()),
+            mockito::Matcher::UrlEncoded("hitsPerPage".into(), "100".into()),
+        ]))
+        .with_header("content-type", "application/json")
+        .with_body(body)
+        .expect(2)
+        .create_async()
+        .await;
+
+    let client = HnClient::with_cache_disabled().with_algolia_base_url(&server.url());
+
+    // The limit applies after ranking, so it picks the two highest-ranked of all hits
+    assert_eq!(client.get_front_page_stories(Some(2)).await.unwrap(), vec![2, 3]);
+    assert_eq!(client.get_front_page_stories(None).await.unwrap(), vec![2, 3, 1]);
+    search.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_spawn_prefetch_warms_cache() {
+    let mut server = mockito::Server::new_async().await;
+    let item = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"dhouston","descendants":71,"id":8863,"score":111,"time":1175714200,"title":"My YC app: Dropbox - Throw away your USB drive","type":"story","url":"http://www.getdropbox.com/u/2/screencast.html"}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_base_url(&server.url());
+    client.spawn_prefetch(vec![8863], None).expect("cache is enabled").await.unwrap();
+
+    // The story now comes from the cache
+    let story = client.get_story_details(8863).await.unwrap();
+    assert_eq!(story.score, 111);
+    item.assert_async().await;
+
+    assert!(HnClient::with_cache_disabled().spawn_prefetch(vec![8863], None).is_none());
+}
+
+#[tokio::test]
+async fn test_get_raw_item_bypasses_cache() {
+    let mut server = mockito::Server::new_async().await;
+    let item = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"dhouston","id":8863,"score":111,"time":1175714200,"title":"My YC app: Dropbox","type":"story"}"#)
+        .expect(2)
+        .create_async()
+        .await;
+    let missing = server
+        .mock("GET", "/item/1.json")
+        .with_header("content-type", "application/json")
+        .with_body("null")
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_base_url(&server.url());
+    let raw = client.get_raw_item(8863).await.unwrap();
+    assert_eq!(raw["by"], "dhouston");
+    assert_eq!(raw["type"], "story");
+    client.get_raw_item(8863).await.unwrap();
+    assert!(client.get_raw_item(1).await.is_err());
+
+    item.assert_async().await;
+    missing.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_refresh_story_details_returns_previous_copy() {
+    let mut server = mockito::Server::new_async().await;
+    let first = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"dhouston","descendants":40,"id":8863,"score":105,"time":1175714200,"title":"My YC app: Dropbox","type":"story"}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_base_url(&server.url());
+    let (previous, story) = client.refresh_story_details(8863).await.unwrap();
+    assert!(previous.is_none());
+    assert_eq!(story.score, 105);
+    first.assert_async().await;
+    first.remove_async().await;
+
+    let second = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"dhouston","descendants":48,"id":8863,"score":120,"time":1175714200,"title":"My YC app: Dropbox","type":"story"}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let (previous, story) = client.refresh_story_details(8863).await.unwrap();
+    assert_eq!(previous.map(|story| story.score), Some(105));
+    assert_eq!(story.score, 120);
+    second.assert_async().await;
+
+    // The fresh copy replaced the cached one
+    assert_eq!(client.get_story_details(8863).await.unwrap().number_of_comments, 48);
+}
+
+#[tokio::test]
+async fn test_get_submissions_by_author() {
+    let mut server = mockito::Server::new_async().await;
+    let search = server
+        .mock("GET", "/search_by_date")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("tags".into(), "author_pg,(story,comment)".into()),
+            mockito::Matcher::UrlEncoded("hitsPerPage".into(), "2".into()),
+        ]))
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"hits":[
+                {"objectID":"121016","author":"pg","created_at_i":1203651012,"comment_text":"Arc is a lot less popular than Lisp was in the 80s.","story_id":121003,"story_title":"Ask HN: The Arc Effect"},
+                {"objectID":"532928","title":"Why TV Lost","url":"http://paulgraham.com/convergence.html","author":"pg","points":220,"num_comments":95,"created_at_i":1238079860}
+            ],"nbHits":2}"#,
+        )
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_algolia_base_url(&server.url());
+    let hits = client.get_submissions_by_author("pg", "(story,comment)", 2).await.unwrap();
+    search.assert_async().await;
+
+    assert!(hits[0].to_story().is_none());
+    let comment = HnClient::format_submission_hit(&hits[0]);
+    assert!(comment.starts_with("Story: Ask HN: The Arc Effect (ID: 121003)\nBy: pg\n"));
+
+    let story = HnClient::format_submission_hit(&hits[1]);
+    assert!(story.starts_with("Title: Why TV Lost\nURL: http://paulgraham.com/convergence.html\nBy: pg\nScore: 220\n"));
+    assert!(story.contains("Comments: 95\nID: 532928\n"));
+}
+
+#[tokio::test]
+async fn test_search_comments_by_date() {
+    let mut server = mockito::Server::new_async().await;
+    let search = server
+        .mock("GET", "/search_by_date")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("query".into(), "arc".into()),
+            mockito::Matcher::UrlEncoded("tags".into(), "comment".into()),
+        ]))
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"hits":[{"objectID":"121016","author":"pg","comment_text":"Arc is a lot less popular than Lisp was in the 80s."}],"nbHits":1}"#)
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_algolia_base_url(&server.url());
+    let sort = SearchSort::parse(Some(" Date ")).unwrap();
+    let hits = client.search_comments("arc", 10, sort).await.unwrap();
+
+    assert_eq!(hits[0].item_id(), Some(121016));
+    search.assert_async().await;
+    assert_eq!(SearchSort::parse(None).unwrap(), SearchSort::Relevance);
+    assert!(SearchSort::parse(Some("points")).is_err());
+}
+
+#[tokio::test]
+async fn test_search_comments_with_facets() {
+    let mut server = mockito::Server::new_async().await;
+    let search = server
+        .mock("GET", "/search")
+        .match_query(mockito::Matcher::AllOf(vec![
+            mockito::Matcher::UrlEncoded("query".into(), "arc".into()),
+            mockito::Matcher::UrlEncoded("facets".into(), "author,_tags".into()),
+        ]))
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"hits":[{"objectID":"121016","author":"pg","comment_text":"Arc"}],"nbHits":42,
+                "facets":{"author":{"pg":12,"rtm":7,"akkartik":7}}}"#,
+        )
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_algolia_base_url(&server.url());
+    let facets = SearchFacet::parse_list(Some("Author, tags,author")).unwrap();
+    let response = client
+        .search_comments_with_facets("arc", 10, SearchSort::Relevance, &facets)
+        .await
+        .unwrap();
+    search.assert_async().await;
+
+    assert_eq!(response.nb_hits, 42);
+    assert_eq!(
+        HnClient::format_facets(&response.facets, &facets),
+        "Top authors: pg (12), akkartik (7), rtm (7)\nTop tags: [none]\n"
+    );
+    assert!(SearchFacet::parse_list(Some("points")).is_err());
+    assert!(SearchFacet::parse_list(None).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_fetch_article_excerpt() {
+    let mut server = mockito::Server::new_async().await;
+    let paragraph = "Lorem ipsum dolor sit amet. ".repeat(40);
+    let page = server
+        .mock("GET", "/post.html")
+        .with_header("content-type", "text/html; charset=utf-8")
+        .with_body(format!("<html><head><title>Post</title></head><body><p>{}</p></body></html>", paragraph))
+        .create_async()
+        .await;
+    let pdf = server
+        .mock("GET", "/paper.pdf")
+        .with_header("content-type", "application/pdf")
+        .with_body("%PDF-1.4")
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_private_article_hosts();
+    let excerpt = client
+        .fetch_article_excerpt(&format!("{}/post.html", server.url()))
+        .await
+        .unwrap();
+    assert!(excerpt.starts_with("Lorem ipsum dolor sit amet. Lorem"));
+    assert!(excerpt.ends_with("..."));
+    assert!(excerpt.chars().count() <= article::ARTICLE_EXCERPT_CHARS + 3);
+    page.assert_async().await;
+
+    let error = client
+        .fetch_article_excerpt(&format!("{}/paper.pdf", server.url()))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("application/pdf"));
+    pdf.assert_async().await;
+
+    assert!(client.fetch_article_excerpt("ftp://example.com/file").await.is_err());
+}
+
+#[tokio::test]
+async fn test_fetch_article_excerpt_refuses_private_hosts() {
+    let mut server = mockito::Server::new_async().await;
+    let page = server.mock("GET", mockito::Matcher::Any).expect(0).create_async().await;
+
+    let client = HnClient::new();
+    let port = server.host_with_port().rsplit(':').next().unwrap().to_string();
+    for url in [
+        server.url(),
+        format!("http://localhost:{}/", port),
+        format!("http://[::ffff:127.0.0.1]:{}/", port),
+        "http://169.254.169.254/latest/meta-data/".to_string(),
+    ] {
+        let error = client.fetch_article_excerpt(&url).await.unwrap_err();
+        assert!(error.to_string().contains("public address"), "{}: {}", url, error);
+    }
+    page.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_get_users_reports_missing_users() {
+    let mut server = mockito::Server::new_async().await;
+    let pg = server
+        .mock("GET", "/user/pg.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"pg","karma":157316,"created":1160418092,"submitted":[8863]}"#)
+        .create_async()
+        .await;
+    let missing = server
+        .mock("GET", "/user/nosuchuser.json")
+        .with_header("content-type", "application/json")
+        .with_body("null")
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_base_url(&server.url());
+    let results = client.get_users(&["pg".to_string(), "nosuchuser".to_string()], 5).await;
+
+    assert_eq!(results[0].0, "pg");
+    assert_eq!(results[0].1.as_ref().unwrap().as_ref().unwrap().karma, 157316);
+    assert_eq!(results[1].0, "nosuchuser");
+    assert!(results[1].1.as_ref().unwrap().is_none());
+    pg.assert_async().await;
+    missing.assert_async().await;
+}
+
+#[test]
+fn test_format_karma_table() {
+    let user = |id: &str, karma| HnUser { id: id.to_string(), karma, created: None };
+    let output = HnClient::format_karma_table(
+        &[user("dang", 45000), user("pg", 157316)],
+        &["nosuchuser".to_string()],
+        &[],
+    );
+    assert_eq!(output, "pg: 157316\ndang: 45000\n\n(not found: nosuchuser)\n");
+    assert_eq!(HnClient::format_karma_table(&[], &[], &["pg".to_string()]), "No users found\n\n(could not be fetched: pg)\n");
+}
+
+#[test]
+fn test_format_comment_histogram() {
+    let story = |number_of_comments| HackerNewsStory {
+        id: 8863,
+        title: "My YC app: Dropbox - Throw away your USB drive".to_string(),
+        url: String::new(),
+        text: String::new(),
+        by: "dhouston".to_string(),
+        score: 111,
+        created_at: OffsetDateTime::UNIX_EPOCH,
+        number_of_comments,
+        comments: Vec::new(),
+    };
+    let stories = [story(0), story(10), story(11), story(200), story(201), story(4000)];
+    assert_eq!(
+        HnClient::format_comment_histogram(&stories),
+        "  0-10 | ## 2\n 11-50 | # 1\n51-200 | # 1\n  201+ | ## 2\n"
+    );
+    assert!(HnClient::format_comment_histogram(&[]).starts_with("  0-10 | 0\n"));
+}
+
+#[tokio::test]
+async fn test_cache_file_round_trip() {
+    let mut server = mockito::Server::new_async().await;
+    let item = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"dhouston","descendants":71,"id":8863,"score":111,"time":1175714200,"title":"My YC app: Dropbox - Throw away your USB drive","type":"story","url":"http://www.getdropbox.com/u/2/screencast.html"}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let dir = temp_dir::TempDir::new().unwrap();
+    let path = dir.child("cache.json");
+
+    let client = HnClient::new().with_base_url(&server.url());
+    client.get_story_details(8863).await.unwrap();
+    assert_eq!(client.save_cache_file(&path).await.unwrap(), 1);
+
+    // A fresh client answers from the loaded file without calling the API
+    let restored = HnClient::new().with_base_url(&server.url());
+    assert_eq!(restored.load_cache_file(&path).await.unwrap(), 1);
+    let story = restored.get_story_details(8863).await.unwrap();
+    assert_eq!(story.score, 111);
+    assert_eq!(story.created_at.unix_timestamp(), 1175714200);
+    item.assert_async().await;
+
+    assert!(HnClient::with_cache_disabled().load_cache_file(&path).await.is_err());
+}
+
+#[tokio::test]
+async fn test_get_ancestors_stops_at_missing_parent() {
+    let mut server = mockito::Server::new_async().await;
+    let parent = server
+        .mock("GET", "/item/9224.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"pg","id":9224,"parent":9000,"text":"Nice work.","time":1175714300,"type":"comment"}"#)
+        .create_async()
+        .await;
+    let missing = server
+        .mock("GET", "/item/9000.json")
+        .with_status(500)
+        .create_async()
+        .await;
+
+    let client = HnClient::new().with_base_url(&server.url());
+    let reply: HnItem = serde_json::from_str(r#"{"by":"dhouston","id":9300,"parent":9224,"text":"Thanks!","type":"comment"}"#).unwrap();
+    let (ancestors, note) = client.get_ancestors(&reply, MAX_ANCESTOR_DEPTH).await;
+
+    assert_eq!(ancestors.iter().map(|item| item.id).collect::<Vec<_>>(), vec![9224]);
+    assert_eq!(note.as_deref(), Some("could not fetch item 9000"));
+    parent.assert_async().await;
+    missing.assert_async().await;
+
+    let (ancestors, note) = client.get_ancestors(&reply, 0).await;
+    assert!(ancestors.is_empty());
+    assert_eq!(note.as_deref(), Some("stopped after 0 parents"));
+}
+
+#[test]
+fn test_format_thread_path() {
+    let item = |json: &str| serde_json::from_str::<HnItem>(json).unwrap();
+    let story = item(r#"{"by":"dhouston","id":8863,"title":"My YC app: Dropbox - Throw away your USB drive","type":"story"}"#);
+    let comment = item(r#"{"by":"pg","id":9224,"parent":8863,"text":"Nice work.<p>Can't wait to try it.","type":"comment"}"#);
+    let reply = item(r#"{"by":"dhouston","id":9300,"parent":9224,"text":"Thanks!","type":"comment"}"#);
+
+    assert_eq!(
+        HnClient::format_thread_path(&[story, comment], &reply, None),
+        "Thread (root first):\n1. Story: My YC app: Dropbox - Throw away your USB drive (ID: 8863)\n2. pg: Nice work. Can't wait to try it. (ID: 9224)\n3. This comment (ID: 9300)\n"
+    );
+    assert!(HnClient::format_thread_path(&[], &reply, Some("could not fetch item 9224"))
+        .starts_with("Thread (root first):\n1. [could not fetch item 9224]\n2. This comment"));
+    assert!(HnClient::format_item(&reply).starts_with("Type: comment\nText: Thanks!\nBy: dhouston\n"));
+}
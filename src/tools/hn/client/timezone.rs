@@ -0,0 +1,61 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Offset, TimeZone};
+use chrono_tz::Tz;
+use std::sync::OnceLock;
+use time::{OffsetDateTime, UtcOffset};
+
+// Time zone of the dates in tool output; UTC until `set_display_timezone` is called
+static DISPLAY_TIMEZONE: OnceLock<Tz> = OnceLock::new();
+
+/// Show dates in tool output in this IANA time zone (e.g. `Asia/Tokyo`) instead of UTC.
+/// Meant to be called once at startup; fails for unknown names or if already set.
+pub fn set_display_timezone(name: &str) -> Result<()> {
+    let timezone: Tz = name
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Unknown time zone '{}' (expected an IANA name such as 'Europe/Berlin')", name))?;
+    DISPLAY_TIMEZONE
+        .set(timezone)
+        .map_err(|_| anyhow!("The display time zone is already set"))
+}
+
+// Convert a timestamp to the display time zone
+pub fn to_display_time(time: OffsetDateTime) -> OffsetDateTime {
+    match DISPLAY_TIMEZONE.get() {
+        Some(timezone) => in_timezone(time, timezone),
+        None => time,
+    }
+}
+
+// The offset depends on the instant (daylight saving time), so look it up per timestamp
+fn in_timezone(time: OffsetDateTime, timezone: &Tz) -> OffsetDateTime {
+    let Some(utc) = DateTime::from_timestamp(time.unix_timestamp(), 0) else {
+        return time;
+    };
+    let offset_seconds = timezone.offset_from_utc_datetime(&utc.naive_utc()).fix().local_minus_utc();
+    UtcOffset::from_whole_seconds(offset_seconds)
+        .map(|offset| time.to_offset(offset))
+        .unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_timezone_follows_daylight_saving_time() {
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        // 2024-01-15 12:00 UTC is winter time (+01:00), 2024-07-15 12:00 UTC summer time (+02:00)
+        let winter = in_timezone(OffsetDateTime::from_unix_timestamp(1705320000).unwrap(), &berlin);
+        let summer = in_timezone(OffsetDateTime::from_unix_timestamp(1721044800).unwrap(), &berlin);
+
+        assert_eq!((winter.hour(), winter.offset().whole_hours()), (13, 1));
+        assert_eq!((summer.hour(), summer.offset().whole_hours()), (14, 2));
+        assert_eq!(summer.unix_timestamp(), 1721044800);
+    }
+
+    #[test]
+    fn test_set_display_timezone_rejects_unknown_names() {
+        assert!(set_display_timezone("Mars/Olympus_Mons").is_err());
+    }
+}
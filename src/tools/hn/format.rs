@@ -0,0 +1,403 @@
+use anyhow::{anyhow, Result};
+use newswrap::items::stories::HackerNewsStory;
+use serde_json::json;
+use time::OffsetDateTime;
+
+use super::client::{html::html_to_text, item_permalink, story_domain, HnClient};
+
+/// Renders stories for tool output. Adding an output format means adding an
+/// implementation here and a name in [`formatter_for`].
+pub trait StoryFormatter: Send + Sync {
+    fn format(&self, story: &HackerNewsStory) -> String;
+
+    /// Render a list of stories, already in display order
+    fn format_list(&self, stories: &[HackerNewsStory]) -> String {
+        if stories.is_empty() {
+            return "No stories found".to_string();
+        }
+        stories
+            .iter()
+            .map(|story| self.format(story))
+            .collect::<Vec<_>>()
+            .join(&self.joiner())
+    }
+
+    /// What goes between two stories in the default [`StoryFormatter::format_list`]
+    fn joiner(&self) -> String {
+        FormatOptions::default().joiner()
+    }
+
+    /// Render a note to append after the stories (e.g. a polling watermark)
+    fn note(&self, note: &str) -> String {
+        format!("\n({})\n", note)
+    }
+
+    /// Attach the `since_id` watermark of a polling call to a rendered list
+    fn format_polled(&self, list: String, newest_id: u32) -> String {
+        list + &self.note(&format!(
+            "Newest story ID: {}; pass it as since_id to get only newer stories",
+            newest_id
+        ))
+    }
+}
+
+/// Layout of the text format, set once for the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Line between two stories (default `---`); an empty separator leaves a blank line
+    pub separator: String,
+    /// `Title:`, `URL:`, ... labels in front of the values (default true). Without them every
+    /// story is the same nine lines (title, URL, text, author, score, date, comments, ID, HN link),
+    /// with empty lines for a missing URL or text.
+    pub field_labels: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            separator: "---".to_string(),
+            field_labels: true,
+        }
+    }
+}
+
+impl FormatOptions {
+    // What goes between two formatted stories, each of which ends with a newline
+    pub fn joiner(&self) -> String {
+        if self.separator.is_empty() {
+            "\n".to_string()
+        } else {
+            format!("\n{}\n", self.separator)
+        }
+    }
+}
+
+/// The `Title:`/`URL:` blocks every tool has always returned
+#[derive(Default)]
+pub struct TextFormatter {
+    options: FormatOptions,
+}
+
+impl TextFormatter {
+    pub fn new(options: FormatOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl StoryFormatter for TextFormatter {
+    fn format(&self, story: &HackerNewsStory) -> String {
+        HnClient::format_story_with(story, &self.options)
+    }
+
+    fn joiner(&self) -> String {
+        self.options.joiner()
+    }
+}
+
+/// One JSON object per story; lists are rendered as a JSON array
+pub struct JsonFormatter;
+
+impl JsonFormatter {
+    fn to_value(story: &HackerNewsStory) -> serde_json::Value {
+        json!({
+            "id": story.id,
+            "title": html_to_text(&story.title),
+            "url": (!story.url.is_empty()).then_some(&story.url),
+            "text": (!story.text.is_empty()).then(|| html_to_text(&story.text)),
+            "by": story.by,
+            "score": story.score,
+            "time": story.created_at.unix_timestamp(),
+            "comments": story.number_of_comments,
+            "hn_url": item_permalink(story.id),
+        })
+    }
+}
+
+impl StoryFormatter for JsonFormatter {
+    fn format(&self, story: &HackerNewsStory) -> String {
+        Self::to_value(story).to_string()
+    }
+
+    fn format_list(&self, stories: &[HackerNewsStory]) -> String {
+        serde_json::Value::Array(stories.iter().map(Self::to_value).collect()).to_string()
+    }
+
+    // Keep the output a plain JSON array; notes only restate what the data already shows
+    fn note(&self, _note: &str) -> String {
+        String::new()
+    }
+
+    // The watermark is data a poller needs, so it goes into an object next to the stories
+    fn format_polled(&self, list: String, newest_id: u32) -> String {
+        match serde_json::from_str::<serde_json::Value>(&list) {
+            Ok(stories) => json!({ "newest_id": newest_id, "stories": stories }).to_string(),
+            Err(_) => list,
+        }
+    }
+}
+
+/// Markdown list items with a linked title, for pasting into notes or chat
+pub struct MarkdownFormatter;
+
+impl StoryFormatter for MarkdownFormatter {
+    fn format(&self, story: &HackerNewsStory) -> String {
+        // Ask HN and similar text posts link to their discussion instead
+        let (link, discussion) = if story.url.is_empty() {
+            (item_permalink(story.id), String::new())
+        } else {
+            (story.url.clone(), format!(" · [discussion]({})", item_permalink(story.id)))
+        };
+        let age = (OffsetDateTime::now_utc() - story.created_at).whole_seconds();
+
+        let mut output = format!(
+            "- [{}]({})\n  *by {} · {} points · {} comments · {}*{}\n",
+            escape_markdown_link_text(&html_to_text(&story.title)),
+            link,
+            story.by,
+            story.score,
+            story.number_of_comments,
+            format_age(age),
+            discussion
+        );
+        if !story.text.is_empty() {
+            output.push('\n');
+            for line in html_to_text(&story.text).lines() {
+                if line.is_empty() {
+                    output.push_str("  >\n");
+                } else {
+                    output.push_str(&format!("  > {}\n", line));
+                }
+            }
+        }
+        output
+    }
+
+    fn format_list(&self, stories: &[HackerNewsStory]) -> String {
+        if stories.is_empty() {
+            return "No stories found".to_string();
+        }
+        stories
+            .iter()
+            .map(|story| self.format(story))
+            .collect::<Vec<_>>()
+            .concat()
+    }
+}
+
+/// One `[score] title — domain (id)` line per story, for scanning long lists
+pub struct CompactFormatter;
+
+impl StoryFormatter for CompactFormatter {
+    fn format(&self, story: &HackerNewsStory) -> String {
+        // Text posts have no domain
+        let domain = story_domain(&story.url).map(|domain| format!(" — {}", domain)).unwrap_or_default();
+        format!("[{}] {}{} ({})", story.score, html_to_text(&story.title), domain, story.id)
+    }
+
+    fn format_list(&self, stories: &[HackerNewsStory]) -> String {
+        if stories.is_empty() {
+            return "No stories found".to_string();
+        }
+        stories
+            .iter()
+            .map(|story| self.format(story))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Brackets in a title would end the link text early
+fn escape_markdown_link_text(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+// Coarse relative age like HN shows it ("5 minutes ago", "3 hours ago")
+fn format_age(seconds: i64) -> String {
+    let (value, unit) = match seconds {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86400 => (s / 3600, "hour"),
+        s if s < 86400 * 365 => (s / 86400, "day"),
+        s => (s / (86400 * 365), "year"),
+    };
+    if value == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", value, unit)
+    }
+}
+
+/// Render `stories` like [`StoryFormatter::format_list`], but drop stories from the end
+/// (keeping at least one) until the output and its truncation note fit in `max_bytes`
+pub fn format_list_within(formatter: &dyn StoryFormatter, stories: &[HackerNewsStory], max_bytes: Option<usize>) -> String {
+    let kept = count_within(formatter, stories, max_bytes);
+    if kept == stories.len() {
+        return formatter.format_list(stories);
+    }
+    truncated_list(formatter, stories, kept)
+}
+
+/// Number of leading `stories` that [`format_list_within`] keeps
+pub fn count_within(formatter: &dyn StoryFormatter, stories: &[HackerNewsStory], max_bytes: Option<usize>) -> usize {
+    let Some(max_bytes) = max_bytes else {
+        return stories.len();
+    };
+    if formatter.format_list(stories).len() <= max_bytes {
+        return stories.len();
+    }
+    (1..stories.len())
+        .rev()
+        .find(|&kept| truncated_list(formatter, stories, kept).len() <= max_bytes)
+        .unwrap_or(stories.len().min(1))
+}
+
+// The first `kept` stories followed by a note on how many were left out
+fn truncated_list(formatter: &dyn StoryFormatter, stories: &[HackerNewsStory], kept: usize) -> String {
+    let omitted = stories.len() - kept;
+    let mut truncated = formatter.format_list(&stories[..kept]);
+    truncated.push_str(&formatter.note(&format!(
+        "output truncated; {} {} omitted",
+        omitted,
+        if omitted == 1 { "story" } else { "stories" }
+    )));
+    truncated
+}
+
+/// Look up the formatter for a tool's `format` parameter (default `text`); `options` only
+/// apply to the text format
+pub fn formatter_for(format: Option<&str>, options: &FormatOptions) -> Result<Box<dyn StoryFormatter>> {
+    match format.map(str::trim).unwrap_or("text").to_ascii_lowercase().as_str() {
+        "" | "text" => Ok(Box::new(TextFormatter::new(options.clone()))),
+        "json" => Ok(Box::new(JsonFormatter)),
+        "markdown" | "md" => Ok(Box::new(MarkdownFormatter)),
+        "compact" => Ok(Box::new(CompactFormatter)),
+        other => Err(anyhow!("Unsupported format '{}' (expected 'text', 'json', 'markdown' or 'compact')", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story() -> HackerNewsStory {
+        HackerNewsStory {
+            id: 8863,
+            title: "My YC app: Dropbox - Throw away your USB drive".to_string(),
+            url: "http://www.getdropbox.com/u/2/screencast.html".to_string(),
+            text: String::new(),
+            by: "dhouston".to_string(),
+            score: 111,
+            created_at: OffsetDateTime::from_unix_timestamp(1175714200).unwrap(),
+            number_of_comments: 71,
+            comments: vec![9224, 8917],
+        }
+    }
+
+    #[test]
+    fn test_formatter_for() {
+        let options = FormatOptions::default();
+        assert!(formatter_for(None, &options).unwrap().format(&story()).starts_with("Title: My YC app"));
+        assert!(formatter_for(Some(" JSON "), &options).unwrap().format(&story()).starts_with('{'));
+        assert!(formatter_for(Some("yaml"), &options).is_err());
+    }
+
+    #[test]
+    fn test_json_formatter_list() {
+        let output = JsonFormatter.format_list(&[story()]);
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value[0]["id"], 8863);
+        assert_eq!(value[0]["score"], 111);
+        assert_eq!(value[0]["time"], 1175714200);
+        assert_eq!(value[0]["text"], serde_json::Value::Null);
+        assert_eq!(value[0]["hn_url"], "https://news.ycombinator.com/item?id=8863");
+        assert_eq!(JsonFormatter.format_list(&[]), "[]");
+    }
+
+    #[test]
+    fn test_markdown_formatter() {
+        let output = MarkdownFormatter.format(&story());
+        assert!(output.starts_with(
+            "- [My YC app: Dropbox - Throw away your USB drive](http://www.getdropbox.com/u/2/screencast.html)\n  *by dhouston · 111 points · 71 comments · "
+        ));
+        assert!(output.ends_with(" · [discussion](https://news.ycombinator.com/item?id=8863)\n"));
+        assert!(!output.contains('>'));
+
+        let ask = HackerNewsStory {
+            title: "Ask HN: [Serious] Where do you host?".to_string(),
+            url: String::new(),
+            text: "Curious what people use.<p>Thanks!".to_string(),
+            ..story()
+        };
+        let output = MarkdownFormatter.format(&ask);
+        assert!(output.starts_with("- [Ask HN: \\[Serious\\] Where do you host?](https://news.ycombinator.com/item?id=8863)\n"));
+        assert!(output.ends_with("\n  > Curious what people use.\n  >\n  > Thanks!\n"));
+    }
+
+    #[test]
+    fn test_compact_formatter() {
+        let ask = HackerNewsStory {
+            id: 121003,
+            title: "Ask HN: The Arc Effect".to_string(),
+            url: String::new(),
+            score: 25,
+            ..story()
+        };
+        assert_eq!(
+            CompactFormatter.format_list(&[story(), ask]),
+            "[111] My YC app: Dropbox - Throw away your USB drive — getdropbox.com (8863)\n[25] Ask HN: The Arc Effect (121003)"
+        );
+        assert!(formatter_for(Some("compact"), &FormatOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_format_list_within() {
+        let stories = [story(), story(), story()];
+        let text = TextFormatter::default();
+        let full = text.format_list(&stories);
+        assert_eq!(format_list_within(&text, &stories, None), full);
+        assert_eq!(format_list_within(&text, &stories, Some(full.len())), full);
+
+        let one_story = text.format(&story()).len();
+        let output = format_list_within(&text, &stories, Some(2 * one_story + 100));
+        assert_eq!(output.matches("Title: ").count(), 2);
+        assert!(output.ends_with("\n(output truncated; 1 story omitted)\n"));
+        assert!(output.len() <= 2 * one_story + 100);
+
+        // The first story is kept even when it alone exceeds the limit
+        let output = format_list_within(&text, &stories, Some(10));
+        assert!(output.starts_with("Title: My YC app"));
+        assert!(output.ends_with("(output truncated; 2 stories omitted)\n"));
+
+        let output = format_list_within(&JsonFormatter, &stories, Some(one_story));
+        assert!(serde_json::from_str::<serde_json::Value>(&output).is_ok());
+    }
+
+    #[test]
+    fn test_text_formatter_options() {
+        let stories = [story(), story()];
+        assert!(TextFormatter::default().format_list(&stories).contains("\n---\nTitle: "));
+
+        let blank_line = TextFormatter::new(FormatOptions { separator: String::new(), field_labels: true });
+        assert!(blank_line.format_list(&stories).contains("HN: https://news.ycombinator.com/item?id=8863\n\nTitle: "));
+
+        let values_only = TextFormatter::new(FormatOptions { separator: "\x0c".to_string(), field_labels: false });
+        let output = values_only.format_list(&stories);
+        let first = output.split("\n\x0c\n").next().unwrap();
+        let lines = first.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[0], "My YC app: Dropbox - Throw away your USB drive");
+        assert_eq!(lines[1], "http://www.getdropbox.com/u/2/screencast.html");
+        assert_eq!(lines[2], "");
+        assert_eq!(lines[3..5], ["dhouston", "111"]);
+        assert_eq!(lines[6..], ["71", "8863", "https://news.ycombinator.com/item?id=8863"]);
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(30), "just now");
+        assert_eq!(format_age(60), "1 minute ago");
+        assert_eq!(format_age(3 * 3600 + 59), "3 hours ago");
+        assert_eq!(format_age(2 * 86400), "2 days ago");
+        assert_eq!(format_age(-5), "just now");
+    }
+}
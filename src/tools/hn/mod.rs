@@ -1,18 +1,66 @@
-use anyhow::Result;
-use tracing::info;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::time::Instant;
 
-use rmcp::{model::*, schemars, tool, ServerHandler};
+use anyhow::{bail, Result};
+use newswrap::items::stories::HackerNewsStory;
+use newswrap::HackerNewsID;
+use tracing::{info, info_span, warn, Instrument, Span};
+
+use rmcp::{
+    handler::server::tool::ToolCallContext, model::*, schemars, service::RequestContext, tool, RoleServer,
+    ServerHandler,
+};
 
 pub mod client;
+pub mod format;
+pub mod text;
+
+use self::format::{count_within, format_list_within, formatter_for, FormatOptions, StoryFormatter, TextFormatter};
+use self::text::truncate_chars;
+use crate::metrics::metrics;
+
+/// Default upper bound for the `chunk_size` parameter of the story list tools
+pub const DEFAULT_MAX_CONCURRENCY: usize = 10;
+
+/// Default upper bound for the `count` of the story list tools and the number of IDs in `hn_stories_by_ids`
+pub const DEFAULT_MAX_STORY_COUNT: usize = 30;
+
+/// Default upper bound for the `count` of the tools that scan a feed (`hn_story_scores`, `hn_trending_domains`,
+/// `hn_comment_count_histogram`) and the number of IDs in `hn_prefetch`
+pub const DEFAULT_MAX_SCAN_COUNT: usize = 100;
+
+/// Default upper bound for the `top_n` comments of `hn_story_with_comments`
+pub const DEFAULT_MAX_COMMENT_COUNT: usize = 20;
+
+// Longest user-supplied string (query, URL, tags) recorded in a tool span
+const MAX_LOGGED_CHARS: usize = 200;
 
 pub struct HnRouter {
     hn_client: client::HnClient,
+    max_concurrency: usize,
+    max_story_count: usize,
+    max_scan_count: usize,
+    max_comment_count: usize,
+    disabled_tools: HashSet<String>,
+    max_response_bytes: Option<usize>,
+    format_options: FormatOptions,
+    // Replaces the built-in `INSTRUCTIONS` in `get_info`
+    instructions: Option<String>,
 }
 
 impl Clone for HnRouter {
     fn clone(&self) -> Self {
         Self {
             hn_client: self.hn_client.clone(),
+            max_concurrency: self.max_concurrency,
+            max_story_count: self.max_story_count,
+            max_scan_count: self.max_scan_count,
+            max_comment_count: self.max_comment_count,
+            disabled_tools: self.disabled_tools.clone(),
+            max_response_bytes: self.max_response_bytes,
+            format_options: self.format_options.clone(),
+            instructions: self.instructions.clone(),
         }
     }
 }
@@ -20,178 +68,1504 @@ impl Clone for HnRouter {
 #[tool(tool_box)]
 impl HnRouter {
     pub fn new(hn_client: client::HnClient) -> Self {
-        Self { hn_client }
+        Self {
+            hn_client,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            max_story_count: DEFAULT_MAX_STORY_COUNT,
+            max_scan_count: DEFAULT_MAX_SCAN_COUNT,
+            max_comment_count: DEFAULT_MAX_COMMENT_COUNT,
+            disabled_tools: HashSet::new(),
+            max_response_bytes: None,
+            format_options: FormatOptions::default(),
+            instructions: None,
+        }
+    }
+
+    /// Set the upper bound for `chunk_size` (values below 1 are treated as 1)
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Set the upper bound for `count` in the story list and search tools, `count_per_feed` in
+    /// `hn_digest`, and the number of IDs in `hn_stories_by_ids` and usernames in `hn_users_karma`
+    /// (values below 1 are treated as 1)
+    pub fn with_max_story_count(mut self, max_story_count: usize) -> Self {
+        self.max_story_count = max_story_count.max(1);
+        self
+    }
+
+    /// Set the upper bound for `count` in `hn_story_scores`, `hn_trending_domains` and
+    /// `hn_comment_count_histogram`, and for the IDs in `hn_prefetch` (values below 1 are treated as 1)
+    pub fn with_max_scan_count(mut self, max_scan_count: usize) -> Self {
+        self.max_scan_count = max_scan_count.max(1);
+        self
+    }
+
+    /// Set the upper bound for `top_n` in `hn_story_with_comments` and `count` in
+    /// `hn_best_comments` (values below 1 are treated as 1)
+    pub fn with_max_comment_count(mut self, max_comment_count: usize) -> Self {
+        self.max_comment_count = max_comment_count.max(1);
+        self
+    }
+
+    /// Set the separator and field labels of the text format
+    pub fn with_format_options(mut self, format_options: FormatOptions) -> Self {
+        self.format_options = format_options;
+        self
+    }
+
+    /// Replace the built-in server instructions sent to clients; the list of disabled tools is
+    /// still appended
+    pub fn with_instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Cap story list output at roughly this many bytes by dropping whole stories from the end
+    pub fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = Some(max_response_bytes);
+        self
+    }
+
+    /// Hide the named tools from `tools/list` and reject calls to them. Fails on a name
+    /// that is not one of this router's tools, so a typo does not leave a tool exposed.
+    pub fn with_disabled_tools<I, S>(mut self, names: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for name in Self::checked_tool_names(names)? {
+            self.disabled_tools.insert(name);
+        }
+        Ok(self)
+    }
+
+    /// Expose only the named tools, disabling every other one. An empty list keeps all tools.
+    pub fn with_enabled_tools<I, S>(mut self, names: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let enabled = Self::checked_tool_names(names)?;
+        if !enabled.is_empty() {
+            self.disabled_tools
+                .extend(Self::tool_names().into_iter().filter(|name| !enabled.contains(name)));
+        }
+        Ok(self)
+    }
+
+    fn tool_names() -> Vec<String> {
+        let mut names = Self::tool_box()
+            .list()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    fn checked_tool_names<I, S>(names: I) -> Result<HashSet<String>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let known = Self::tool_names();
+        let mut checked = HashSet::new();
+        for name in names {
+            let name = name.as_ref().trim();
+            if !known.iter().any(|known| known == name) {
+                bail!("Unknown tool '{}' (available tools: {})", name, known.join(", "));
+            }
+            checked.insert(name.to_string());
+        }
+        Ok(checked)
     }
+
     #[tool(description = "Retrieves the top trending stories from Hacker News (HN is the common abbreviation for Hacker News) with their complete details including title, URL, text, author, score, date, and comment count. Results are sorted by score in descending order. Example: `hn_top_stories(count=3)` returns the three highest-scored stories currently trending on HN, displaying their full details including URLs and comment counts.")]
     async fn hn_top_stories(
         &self,
         #[tool(param)]
-        #[schemars(description = "Number of stories to fetch (1-30, default 10). Controls how many top stories will be returned. Example: 5 will return the 5 highest-scoring top stories. Higher values provide more comprehensive results but take longer to process.")]
+        #[schemars(description = "Number of stories to fetch (default 10, max 30 unless the server was started with a different --max-story-count). Controls how many top stories will be returned. Example: 5 will return the 5 highest-scoring top stories. Higher values provide more comprehensive results but take longer to process.")]
         count: Option<usize>,
 
         #[tool(param)]
-        #[schemars(description = "Number of stories to process in parallel (1-10, default 5). Higher values may speed up retrieval but increase API load. Example: 10 for maximum concurrency, 3 for lighter load on the API. This affects performance but not the actual results.")]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Higher values may speed up retrieval but increase API load. Example: 10 for maximum concurrency, 3 for lighter load on the API. This affects performance but not the actual results.")]
         chunk_size: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Output format: 'text' (default) for readable 'Title:/URL:' blocks separated by '---', 'json' for a JSON array of objects with id, title, url, text, by, score, time (Unix seconds) and comments, 'markdown' for a list of linked titles with a by/points/comments/age line (and quoted text for Ask posts), or 'compact' for one '[score] title — domain (id)' line per story. Example: 'json' when the result will be processed by code, 'markdown' for pasting into notes, 'compact' for scanning 30 stories at a glance.")]
+        format: Option<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to skip from the start of the feed (default 0), for paging through it. Example: offset=10 with count=10 returns feed positions 11-20; stories within the page are still sorted as described above.")]
+        offset: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Warm the cache for the next page (the `count` stories after this one) in the background, so the following call with offset increased by count returns quickly (default false). Costs extra API requests; has no effect when the server runs with --no-cache.")]
+        prefetch: Option<bool>,
+
+        #[tool(param)]
+        #[schemars(description = "Which submissions to return: 'all' (default), 'link' for stories that point to an external URL, or 'text' for text posts such as Ask HN. When filtering, a larger part of the feed is scanned so the result still comes close to `count`. Example: 'link' for a reading list.")]
+        post_type: Option<String>,
     ) -> String {
-        let count = count.unwrap_or(10).min(30);
-        let chunk_size = chunk_size.unwrap_or(5).clamp(1, 10);
+        let count = count.unwrap_or(10).min(self.max_story_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+        let offset = offset.unwrap_or(0);
+        let prefetch = prefetch.unwrap_or(false);
 
-        match self
-            .get_hacker_news_stories(count, chunk_size, |client, limit| async move {
-                client.get_top_stories(Some(limit)).await
-            })
-            .await
-        {
-            Ok(result) => result,
-            Err(e) => format!("Error fetching top stories: {}", e),
-        }
+        let span = info_span!("hn_top_stories", count, offset, chunk_size, prefetch);
+        traced("hn_top_stories", span, async {
+            let formatter = match formatter_for(format.as_deref(), &self.format_options) {
+                Ok(formatter) => formatter,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+            let post_type = match PostType::parse(post_type.as_deref()) {
+                Ok(post_type) => post_type,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+
+            match self
+                .get_hacker_news_stories(
+                    ListOptions { count, offset, chunk_size, prefetch, post_type },
+                    formatter.as_ref(),
+                    |client, limit| async move { client.get_top_stories(Some(limit)).await },
+                )
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => Err(format!("Error fetching top stories: {}", e)),
+            }
+        })
+        .await
     }
 
-    #[tool(description = "Retrieves the most recently submitted stories from Hacker News (HN is the common abbreviation for Hacker News) with their complete details including title, URL, text, author, score, date, and comment count. Useful for discovering brand new content that hasn't been widely seen yet. Results are sorted by score in descending order. Example: `hn_latest_stories(count=2)` would return content like 'Ask HN: Why is Reddit down?' (Score: 42) and 'The Future of Rust Web Development' (Score: 37) that were just submitted minutes ago.")]
+    #[tool(description = "Retrieves the most recently submitted stories from Hacker News (HN is the common abbreviation for Hacker News) with their complete details including title, URL, text, author, score, date, and comment count. Useful for discovering brand new content that hasn't been widely seen yet. Results are sorted by score in descending order. Example: `hn_latest_stories(count=2)` would return content like 'Ask HN: Why is Reddit down?' (Score: 42) and 'The Future of Rust Web Development' (Score: 37) that were just submitted minutes ago. The output ends with the newest story ID seen; pass it back as since_id when polling to get only stories submitted after it: `{\"name\": \"hn_latest_stories\", \"arguments\": {\"since_id\": 39618653}}`.")]
+    // Every tool parameter is a function argument
+    #[allow(clippy::too_many_arguments)]
     async fn hn_latest_stories(
         &self,
         #[tool(param)]
-        #[schemars(description = "Number of stories to fetch (1-30, default 10). Controls how many latest stories will be returned. Example: 15 will return the 15 most recent stories, while 3 will focus only on the very newest submissions with highest scores.")]
+        #[schemars(description = "Number of stories to fetch (default 10, max 30 unless the server was started with a different --max-story-count). Controls how many latest stories will be returned. Example: 15 will return the 15 most recent stories, while 3 will focus only on the very newest submissions with highest scores.")]
         count: Option<usize>,
 
         #[tool(param)]
-        #[schemars(description = "Number of stories to process in parallel (1-10, default 5). Higher values may speed up retrieval but increase API load. Example: 8 for faster retrieval, 2 for minimal API impact. This is particularly useful when fetching many stories at once.")]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Higher values may speed up retrieval but increase API load. Example: 8 for faster retrieval, 2 for minimal API impact. This is particularly useful when fetching many stories at once.")]
         chunk_size: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Output format: 'text' (default) for readable 'Title:/URL:' blocks separated by '---', 'json' for a JSON array of objects with id, title, url, text, by, score, time (Unix seconds) and comments, 'markdown' for a list of linked titles with a by/points/comments/age line (and quoted text for Ask posts), or 'compact' for one '[score] title — domain (id)' line per story. Example: 'json' when the result will be processed by code, 'markdown' for pasting into notes, 'compact' for scanning 30 stories at a glance.")]
+        format: Option<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to skip from the start of the feed (default 0), for paging through it. Example: offset=10 with count=10 returns feed positions 11-20; stories within the page are still sorted as described above.")]
+        offset: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Only return stories with an ID greater than this one (HN IDs increase over time). Use the 'Newest story ID' from a previous call to poll for new submissions; stories left out of a response stay above it, so they are returned by the next poll. If more than `count` new stories arrived, only the newest `count` are returned. With format='json', passing since_id (0 for the first poll) returns {\"newest_id\": N, \"stories\": [...]}. Example: 39618653.")]
+        since_id: Option<u32>,
+
+        #[tool(param)]
+        #[schemars(description = "Warm the cache for the next page (the `count` stories after this one) in the background, so the following call with offset increased by count returns quickly (default false). Costs extra API requests; has no effect when the server runs with --no-cache.")]
+        prefetch: Option<bool>,
+
+        #[tool(param)]
+        #[schemars(description = "Which submissions to return: 'all' (default), 'link' for stories that point to an external URL, or 'text' for text posts such as Ask HN. When filtering, a larger part of the feed is scanned so the result still comes close to `count`. Example: 'link' for a reading list.")]
+        post_type: Option<String>,
     ) -> String {
-        let count = count.unwrap_or(10).min(30);
-        let chunk_size = chunk_size.unwrap_or(5).clamp(1, 10);
+        let count = count.unwrap_or(10).min(self.max_story_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+        let offset = offset.unwrap_or(0);
+        let prefetch = prefetch.unwrap_or(false);
 
-        match self
-            .get_hacker_news_stories(count, chunk_size, |client, limit| async move {
-                client.get_latest_stories(Some(limit)).await
-            })
-            .await
-        {
-            Ok(result) => result,
-            Err(e) => format!("Error fetching latest stories: {}", e),
-        }
+        let span = info_span!("hn_latest_stories", count, offset, chunk_size, since_id, prefetch);
+        traced("hn_latest_stories", span, async {
+            let formatter = match formatter_for(format.as_deref(), &self.format_options) {
+                Ok(formatter) => formatter,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+            let post_type = match PostType::parse(post_type.as_deref()) {
+                Ok(post_type) => post_type,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+
+            // The feed is newest first, so without a watermark the first `offset` IDs plus one
+            // page suffice (two pages when the next one is prefetched)
+            let page_size = post_type.candidate_pool(count);
+            let page_count = if prefetch { 2 * page_size } else { page_size };
+            let limit = if since_id.is_some() { None } else { Some(offset + page_count) };
+            let story_ids = match self.hn_client.get_latest_stories(limit).await {
+                Ok(ids) => ids,
+                Err(e) => return Err(format!("Error fetching latest stories: {}", e)),
+            };
+            let mut story_ids = story_ids
+                .into_iter()
+                .filter(|id| since_id.is_none_or(|since_id| *id > since_id))
+                .skip(offset)
+                .take(page_count)
+                .collect::<Vec<_>>();
+            if prefetch {
+                let next_page = story_ids.split_off(page_size.min(story_ids.len()));
+                self.hn_client.spawn_prefetch(next_page, Some(chunk_size));
+            }
+
+            let list = match self
+                .list_stories(
+                    ListOptions { count, offset: 0, chunk_size, prefetch: false, post_type },
+                    formatter.as_ref(),
+                    |_, _| async move { Ok(story_ids) },
+                )
+                .await
+            {
+                Ok(list) => list,
+                Err(e) => return Err(format!("Error fetching latest stories: {}", e)),
+            };
+            let newest_id = polling_watermark(&list).or(since_id);
+            let output = match newest_id {
+                // Pollers need the watermark even in JSON, where notes are dropped
+                Some(newest_id) if since_id.is_some() => formatter.format_polled(list.output, newest_id),
+                Some(newest_id) => {
+                    list.output
+                        + &formatter.note(&format!(
+                            "Newest story ID: {}; pass it as since_id to get only newer stories",
+                            newest_id
+                        ))
+                }
+                None => list.output,
+            };
+            partial(output, list.failed)
+        })
+        .await
     }
 
     #[tool(description = "Retrieves the highest-quality stories from Hacker News (HN is the common abbreviation for Hacker News) based on a combination of score, comments, and other factors. Returns complete details including title, URL, text, author, score, date, and comment count. Best for finding the most interesting content over a longer time period. Results are sorted by score in descending order. Example: `hn_best_stories(count=2)` might return stories like 'Show HN: Structify – Convert unstructured text to structured data with AI' (Score: 943) and 'The History of Programming Languages Visualized' (Score: 876) that have gained significant attention over days.")]
     async fn hn_best_stories(
         &self,
         #[tool(param)]
-        #[schemars(description = "Number of stories to fetch (1-30, default 10). Controls how many best stories will be returned. Example: 20 will return the 20 highest-quality stories from recent days, while 5 will focus only on the absolute best content. With count=1, you'll get the single highest-quality story.")]
+        #[schemars(description = "Number of stories to fetch (default 10, max 30 unless the server was started with a different --max-story-count). Controls how many best stories will be returned. Example: 20 will return the 20 highest-quality stories from recent days, while 5 will focus only on the absolute best content. With count=1, you'll get the single highest-quality story.")]
+        count: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Higher values may speed up retrieval but increase API load. Example: 7 for balanced performance, 4 for slightly reduced load. Setting chunk_size=1 processes sequentially but puts minimal load on the API.")]
+        chunk_size: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Output format: 'text' (default) for readable 'Title:/URL:' blocks separated by '---', 'json' for a JSON array of objects with id, title, url, text, by, score, time (Unix seconds) and comments, 'markdown' for a list of linked titles with a by/points/comments/age line (and quoted text for Ask posts), or 'compact' for one '[score] title — domain (id)' line per story. Example: 'json' when the result will be processed by code, 'markdown' for pasting into notes, 'compact' for scanning 30 stories at a glance.")]
+        format: Option<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to skip from the start of the feed (default 0), for paging through it. Example: offset=10 with count=10 returns feed positions 11-20; stories within the page are still sorted as described above.")]
+        offset: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Warm the cache for the next page (the `count` stories after this one) in the background, so the following call with offset increased by count returns quickly (default false). Costs extra API requests; has no effect when the server runs with --no-cache.")]
+        prefetch: Option<bool>,
+
+        #[tool(param)]
+        #[schemars(description = "Which submissions to return: 'all' (default), 'link' for stories that point to an external URL, or 'text' for text posts such as Ask HN. When filtering, a larger part of the feed is scanned so the result still comes close to `count`. Example: 'link' for a reading list.")]
+        post_type: Option<String>,
+    ) -> String {
+        let count = count.unwrap_or(10).min(self.max_story_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+        let offset = offset.unwrap_or(0);
+        let prefetch = prefetch.unwrap_or(false);
+
+        let span = info_span!("hn_best_stories", count, offset, chunk_size, prefetch);
+        traced("hn_best_stories", span, async {
+            let formatter = match formatter_for(format.as_deref(), &self.format_options) {
+                Ok(formatter) => formatter,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+            let post_type = match PostType::parse(post_type.as_deref()) {
+                Ok(post_type) => post_type,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+
+            match self
+                .get_hacker_news_stories(
+                    ListOptions { count, offset, chunk_size, prefetch, post_type },
+                    formatter.as_ref(),
+                    |client, limit| async move { client.get_best_stories(Some(limit)).await },
+                )
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => Err(format!("Error fetching best stories: {}", e)),
+            }
+        })
+        .await
+    }
+
+    #[tool(description = "Retrieves 'Ask HN' question posts from Hacker News (HN is the common abbreviation for Hacker News) where users ask the community for advice, opinions, or information. Returns complete details including title, text, author, score, date, and comment count. Particularly useful for finding discussions, questions, and community interactions. Results are sorted by score in descending order. Example: `hn_ask_stories(count=2)` might return questions like 'Ask HN: What productivity tools do you use in 2025?' (Score: 183, Comments: 207) and 'Ask HN: How are you using the new GPT-4o in your workflow?' (Score: 156, Comments: 142).")]
+    async fn hn_ask_stories(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Number of stories to fetch (default 10, max 30 unless the server was started with a different --max-story-count). Controls how many Ask HN stories will be returned. Example: 12 will return the 12 highest-scoring Ask HN stories. Setting count=30 will give you the most comprehensive view of current community questions. Popular Ask HN posts often have many comments, making them valuable for research.")]
+        count: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Higher values may speed up retrieval but increase API load. Example: 6 for moderate concurrency. For Ask HN stories, which often contain more text content, a moderate chunk_size of 4-6 is generally optimal for balanced performance.")]
+        chunk_size: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Output format: 'text' (default) for readable 'Title:/URL:' blocks separated by '---', 'json' for a JSON array of objects with id, title, url, text, by, score, time (Unix seconds) and comments, 'markdown' for a list of linked titles with a by/points/comments/age line (and quoted text for Ask posts), or 'compact' for one '[score] title — domain (id)' line per story. Example: 'json' when the result will be processed by code, 'markdown' for pasting into notes, 'compact' for scanning 30 stories at a glance.")]
+        format: Option<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to skip from the start of the feed (default 0), for paging through it. Example: offset=10 with count=10 returns feed positions 11-20; stories within the page are still sorted as described above.")]
+        offset: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Warm the cache for the next page (the `count` stories after this one) in the background, so the following call with offset increased by count returns quickly (default false). Costs extra API requests; has no effect when the server runs with --no-cache.")]
+        prefetch: Option<bool>,
+
+        #[tool(param)]
+        #[schemars(description = "Which submissions to return: 'all' (default), 'link' for stories that point to an external URL, or 'text' for text posts such as Ask HN. When filtering, a larger part of the feed is scanned so the result still comes close to `count`. Example: 'link' for a reading list.")]
+        post_type: Option<String>,
+    ) -> String {
+        let count = count.unwrap_or(10).min(self.max_story_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+        let offset = offset.unwrap_or(0);
+        let prefetch = prefetch.unwrap_or(false);
+
+        let span = info_span!("hn_ask_stories", count, offset, chunk_size, prefetch);
+        traced("hn_ask_stories", span, async {
+            let formatter = match formatter_for(format.as_deref(), &self.format_options) {
+                Ok(formatter) => formatter,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+            let post_type = match PostType::parse(post_type.as_deref()) {
+                Ok(post_type) => post_type,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+
+            match self
+                .get_hacker_news_stories(
+                    ListOptions { count, offset, chunk_size, prefetch, post_type },
+                    formatter.as_ref(),
+                    |client, limit| async move { client.get_ask_stories(Some(limit)).await },
+                )
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => Err(format!("Error fetching Ask HN stories: {}", e)),
+            }
+        })
+        .await
+    }
+
+    #[tool(description = "Retrieves 'Show HN' posts from Hacker News (HN is the common abbreviation for Hacker News) where users showcase their projects, websites, apps, or creations to get feedback from the community. Returns complete details including title, URL, text, author, score, date, and comment count. Ideal for discovering new projects and innovations. Results are sorted by score in descending order. Example: `hn_show_stories(count=2)` might return projects like 'Show HN: Structify – Convert unstructured text to structured data with AI' (URL: https://github.com/structify/structify) and 'Show HN: LocalLLM – Run powerful language models on consumer hardware' (URL: https://localllm.ai).")]
+    async fn hn_show_stories(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Number of stories to fetch (default 10, max 30 unless the server was started with a different --max-story-count). Controls how many Show HN stories will be returned. Example: 10 will return the 10 highest-scoring Show HN stories. For discovering the widest range of new projects, try count=25, while for finding only the most popular showcases, try count=3. Show HN posts typically include project URLs and descriptions.")]
+        count: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Higher values may speed up retrieval but increase API load. Example: 5 for default concurrency. Since Show HN posts often include links to external sites, a moderate chunk_size of 5 balances speed and API load effectively.")]
+        chunk_size: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Output format: 'text' (default) for readable 'Title:/URL:' blocks separated by '---', 'json' for a JSON array of objects with id, title, url, text, by, score, time (Unix seconds) and comments, 'markdown' for a list of linked titles with a by/points/comments/age line (and quoted text for Ask posts), or 'compact' for one '[score] title — domain (id)' line per story. Example: 'json' when the result will be processed by code, 'markdown' for pasting into notes, 'compact' for scanning 30 stories at a glance.")]
+        format: Option<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to skip from the start of the feed (default 0), for paging through it. Example: offset=10 with count=10 returns feed positions 11-20; stories within the page are still sorted as described above.")]
+        offset: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Warm the cache for the next page (the `count` stories after this one) in the background, so the following call with offset increased by count returns quickly (default false). Costs extra API requests; has no effect when the server runs with --no-cache.")]
+        prefetch: Option<bool>,
+
+        #[tool(param)]
+        #[schemars(description = "Which submissions to return: 'all' (default), 'link' for stories that point to an external URL, or 'text' for text posts such as Ask HN. When filtering, a larger part of the feed is scanned so the result still comes close to `count`. Example: 'link' for a reading list.")]
+        post_type: Option<String>,
+    ) -> String {
+        let count = count.unwrap_or(10).min(self.max_story_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+        let offset = offset.unwrap_or(0);
+        let prefetch = prefetch.unwrap_or(false);
+
+        let span = info_span!("hn_show_stories", count, offset, chunk_size, prefetch);
+        traced("hn_show_stories", span, async {
+            let formatter = match formatter_for(format.as_deref(), &self.format_options) {
+                Ok(formatter) => formatter,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+            let post_type = match PostType::parse(post_type.as_deref()) {
+                Ok(post_type) => post_type,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+
+            match self
+                .get_hacker_news_stories(
+                    ListOptions { count, offset, chunk_size, prefetch, post_type },
+                    formatter.as_ref(),
+                    |client, limit| async move { client.get_show_stories(Some(limit)).await },
+                )
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => Err(format!("Error fetching Show HN stories: {}", e)),
+            }
+        })
+        .await
+    }
+
+    #[tool(description = "Builds a 'morning briefing' of Hacker News (HN is the common abbreviation for Hacker News): fetches the top, Ask HN and Show HN feeds concurrently and returns one report with a '## Top', '## Ask HN' and '## Show HN' section. Each section lists stories with their complete details, sorted by score in descending order. Faster than calling hn_top_stories, hn_ask_stories and hn_show_stories one after another. Example usage: `{\"name\": \"hn_digest\", \"arguments\": {}}`. Longer sections: `{\"name\": \"hn_digest\", \"arguments\": {\"count_per_feed\": 10}}`")]
+    async fn hn_digest(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Number of stories in each section (default 5, max 30 unless the server was started with a different --max-story-count). Example: 3 for a quick overview.")]
+        count_per_feed: Option<usize>,
+    ) -> String {
+        let count = count_per_feed.unwrap_or(5).clamp(1, self.max_story_count);
+        let options = ListOptions {
+            count,
+            offset: 0,
+            chunk_size: self.clamp_chunk_size(None),
+            prefetch: false,
+            post_type: PostType::All,
+        };
+
+        let span = info_span!("hn_digest", count);
+        traced("hn_digest", span, async {
+            let text = TextFormatter::new(self.format_options.clone());
+            let (top, ask, show) = tokio::join!(
+                self.get_hacker_news_stories(options, &text, |client, limit| async move {
+                    client.get_top_stories(Some(limit)).await
+                }),
+                self.get_hacker_news_stories(options, &text, |client, limit| async move {
+                    client.get_ask_stories(Some(limit)).await
+                }),
+                self.get_hacker_news_stories(options, &text, |client, limit| async move {
+                    client.get_show_stories(Some(limit)).await
+                }),
+            );
+
+            // A failing feed only empties its own section, but the call still counts as failed
+            let mut failed = false;
+            let digest = [
+                ("Top", "top stories", top),
+                ("Ask HN", "Ask HN stories", ask),
+                ("Show HN", "Show HN stories", show),
+            ]
+            .into_iter()
+            .map(|(heading, feed, result)| {
+                let body = match result {
+                    Ok(Ok(body)) => body,
+                    Ok(Err(body)) => {
+                        failed = true;
+                        body
+                    }
+                    Err(e) => {
+                        failed = true;
+                        format!("Error fetching {}: {}", feed, e)
+                    }
+                };
+                format!("## {}\n\n{}\n", heading, body.trim_end())
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+            partial(digest, failed)
+        })
+        .await
+    }
+
+    #[tool(description = "Retrieves a compact score leaderboard for a Hacker News (HN is the common abbreviation for Hacker News) feed: one line per story with its score, title and ID, sorted by score in descending order. Much smaller than the full story tools, so it suits large counts; use hn_story_by_id or hn_stories_by_ids afterwards for details of interesting stories. Example usage: `{\"name\": \"hn_story_scores\", \"arguments\": {\"count\": 50}}`. For another feed: `{\"name\": \"hn_story_scores\", \"arguments\": {\"feed\": \"best\", \"count\": 100}}`")]
+    async fn hn_story_scores(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Feed to rank: 'top' (default), 'new', 'best', 'ask' or 'show'.")]
+        feed: Option<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to include (default 30, max 100 unless the server was started with a different --max-scan-count). Example: 100 for a long leaderboard.")]
+        count: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Example: 10 when requesting many stories.")]
+        chunk_size: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to skip from the start of the feed (default 0), for paging through it. Example: offset=10 with count=10 returns feed positions 11-20; stories within the page are still sorted as described above.")]
+        offset: Option<usize>,
+    ) -> String {
+        let feed = feed.unwrap_or_else(|| "top".to_string());
+        let count = count.unwrap_or(30).clamp(1, self.max_scan_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+        let offset = offset.unwrap_or(0);
+
+        let span = info_span!("hn_story_scores", feed = truncate_chars(&feed, MAX_LOGGED_CHARS), count, offset, chunk_size);
+        traced("hn_story_scores", span, async {
+            let story_ids = match feed.trim().to_ascii_lowercase().as_str() {
+                "top" => self.hn_client.get_top_stories(Some(offset + count)).await,
+                "new" | "latest" => self.hn_client.get_latest_stories(Some(offset + count)).await,
+                "best" => self.hn_client.get_best_stories(Some(offset + count)).await,
+                "ask" => self.hn_client.get_ask_stories(Some(offset + count)).await,
+                "show" => self.hn_client.get_show_stories(Some(offset + count)).await,
+                other => {
+                    return Err(format!(
+                        "Error: unknown feed '{}' (expected 'top', 'new', 'best', 'ask' or 'show')",
+                        other
+                    ))
+                }
+            };
+            let story_ids: Vec<_> = match story_ids {
+                Ok(ids) => ids.into_iter().skip(offset).collect(),
+                Err(e) => return Err(format!("Error fetching {} stories: {}", feed, e)),
+            };
+
+            let requested = story_ids.len();
+            let mut stories = match self
+                .hn_client
+                .get_stories_details(story_ids, Some(chunk_size))
+                .await
+            {
+                Ok(stories) => stories,
+                Err(e) => return Err(format!("Error fetching {} stories: {}", feed, e)),
+            };
+            if stories.is_empty() {
+                return match shortfall_note(requested, 0) {
+                    Some(note) => Err(format!("No stories found\n({})\n", note)),
+                    None => Ok("No stories found".to_string()),
+                };
+            }
+
+            stories.sort_by(|a, b| b.score.cmp(&a.score));
+            let width = stories[0].score.to_string().len().max("score".len());
+            let mut table = format!("{:>width$}  title (id)\n", "score");
+            for story in &stories {
+                table.push_str(&format!(
+                    "{:>width$}  {} ({})\n",
+                    story.score,
+                    client::html::html_to_text(&story.title),
+                    story.id
+                ));
+            }
+            if let Some(note) = shortfall_note(requested, stories.len()) {
+                table.push_str(&format!("({})\n", note));
+            }
+            partial(table, stories.len() < requested)
+        })
+        .await
+    }
+
+    #[tool(description = "Shows which websites dominate Hacker News (HN is the common abbreviation for Hacker News) right now: fetches the top stories, groups them by the domain of their URL and returns the domains ranked by how many stories link to them, with the IDs of those stories. Text posts such as Ask HN have no domain and are counted separately. Example usage: `{\"name\": \"hn_trending_domains\", \"arguments\": {}}`. Scanning more stories: `{\"name\": \"hn_trending_domains\", \"arguments\": {\"count\": 100}}`")]
+    async fn hn_trending_domains(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Number of top stories to scan (default 30, max 100 unless the server was started with a different --max-scan-count; the HN front page shows 30). Example: 100 for a broader view.")]
+        count: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Example: 10 when scanning many stories.")]
+        chunk_size: Option<usize>,
+    ) -> String {
+        let count = count.unwrap_or(30).clamp(1, self.max_scan_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+
+        let span = info_span!("hn_trending_domains", count, chunk_size);
+        traced("hn_trending_domains", span, async {
+            let story_ids = match self.hn_client.get_top_stories(Some(count)).await {
+                Ok(ids) => ids,
+                Err(e) => return Err(format!("Error fetching top stories: {}", e)),
+            };
+            let requested = story_ids.len();
+            let stories = match self
+                .hn_client
+                .get_stories_details(story_ids, Some(chunk_size))
+                .await
+            {
+                Ok(stories) => stories,
+                Err(e) => return Err(format!("Error fetching top stories: {}", e)),
+            };
+
+            let mut domains: HashMap<String, Vec<HackerNewsID>> = HashMap::new();
+            let mut text_posts = 0;
+            for story in &stories {
+                match client::story_domain(&story.url) {
+                    Some(domain) => domains.entry(domain).or_default().push(story.id),
+                    None => text_posts += 1,
+                }
+            }
+            if domains.is_empty() && text_posts == 0 {
+                return Ok("No stories found".to_string());
+            }
+
+            // Most stories first; ties in alphabetical order for stable output
+            let mut domains = domains.into_iter().collect::<Vec<_>>();
+            domains.sort_by(|(a_domain, a_ids), (b_domain, b_ids)| {
+                b_ids.len().cmp(&a_ids.len()).then_with(|| a_domain.cmp(b_domain))
+            });
+
+            let mut result = format!("Domains among the top {} stories:\n", stories.len());
+            for (domain, ids) in &domains {
+                let id_list = ids.iter().map(u32::to_string).collect::<Vec<_>>().join(", ");
+                result.push_str(&format!("{:>3}  {} (IDs: {})\n", ids.len(), domain, id_list));
+            }
+            if text_posts > 0 {
+                result.push_str(&format!("{:>3}  [text posts without a URL]\n", text_posts));
+            }
+            if let Some(note) = shortfall_note(requested, stories.len()) {
+                result.push_str(&format!("({})\n", note));
+            }
+            partial(result, stories.len() < requested)
+        })
+        .await
+    }
+
+    #[tool(description = "Shows how discussion is spread across the top Hacker News (HN is the common abbreviation for Hacker News) stories: fetches the top stories and returns a small text histogram of how many fall into each comment-count bucket (0-10, 11-50, 51-200, 201+), followed by the most discussed story. Useful for telling whether stories are driving discussion or just collecting upvotes. Example usage: `{\"name\": \"hn_comment_count_histogram\", \"arguments\": {}}`. Scanning more stories: `{\"name\": \"hn_comment_count_histogram\", \"arguments\": {\"count\": 100}}`")]
+    async fn hn_comment_count_histogram(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Number of top stories to scan (default 30, max 100 unless the server was started with a different --max-scan-count; the HN front page shows 30). Example: 100 for a broader view.")]
+        count: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Example: 10 when scanning many stories.")]
+        chunk_size: Option<usize>,
+    ) -> String {
+        let count = count.unwrap_or(30).clamp(1, self.max_scan_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+
+        let span = info_span!("hn_comment_count_histogram", count, chunk_size);
+        traced("hn_comment_count_histogram", span, async {
+            let story_ids = match self.hn_client.get_top_stories(Some(count)).await {
+                Ok(ids) => ids,
+                Err(e) => return Err(format!("Error fetching top stories: {}", e)),
+            };
+            let requested = story_ids.len();
+            let stories = match self
+                .hn_client
+                .get_stories_details(story_ids, Some(chunk_size))
+                .await
+            {
+                Ok(stories) => stories,
+                Err(e) => return Err(format!("Error fetching top stories: {}", e)),
+            };
+            let Some(most_discussed) = stories.iter().max_by_key(|story| story.number_of_comments) else {
+                return Ok("No stories found".to_string());
+            };
+
+            let mut result = format!("Comment counts among the top {} stories:\n", stories.len());
+            result.push_str(&client::HnClient::format_comment_histogram(&stories));
+            result.push_str(&format!(
+                "\nMost discussed: {} ({} comments, {} points, ID: {})\n",
+                client::html::html_to_text(&most_discussed.title),
+                most_discussed.number_of_comments,
+                most_discussed.score,
+                most_discussed.id
+            ));
+            if let Some(note) = shortfall_note(requested, stories.len()) {
+                result.push_str(&format!("({})\n", note));
+            }
+            partial(result, stories.len() < requested)
+        })
+        .await
+    }
+
+    #[tool(description = "Retrieves the stories currently on the Hacker News (HN is the common abbreviation for Hacker News) front page, in the order they appear on news.ycombinator.com. Unlike hn_top_stories, which sorts by raw score, this ranks stories with HN's gravity formula so newer stories with fewer points can appear above older, higher-scored ones. Returns complete details including title, URL, text, author, score, date, and comment count. Use this when you want to know what readers see on the HN homepage right now. Example usage: `{\"name\": \"hn_front_page\", \"arguments\": {\"count\": 10}}`")]
+    async fn hn_front_page(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Number of front page stories to fetch (default 10, max 30 unless the server was started with a different --max-story-count). The HN front page shows 30 stories. Example: 5 returns the first five stories on the homepage.")]
+        count: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Higher values may speed up retrieval but increase API load. Example: 10 to fetch a full front page quickly.")]
+        chunk_size: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Output format: 'text' (default) for readable 'Title:/URL:' blocks separated by '---', 'json' for a JSON array of objects with id, title, url, text, by, score, time (Unix seconds) and comments, 'markdown' for a list of linked titles with a by/points/comments/age line (and quoted text for Ask posts), or 'compact' for one '[score] title — domain (id)' line per story. Example: 'json' when the result will be processed by code, 'markdown' for pasting into notes, 'compact' for scanning 30 stories at a glance.")]
+        format: Option<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to skip from the start of the feed (default 0), for paging through it. Example: offset=10 with count=10 returns feed positions 11-20; stories within the page are still sorted as described above.")]
+        offset: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Which submissions to return: 'all' (default), 'link' for stories that point to an external URL, or 'text' for text posts such as Ask HN. When filtering, a larger part of the feed is scanned so the result still comes close to `count`. Example: 'link' for a reading list.")]
+        post_type: Option<String>,
+    ) -> String {
+        let count = count.unwrap_or(10).min(self.max_story_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+        let offset = offset.unwrap_or(0);
+
+        let span = info_span!("hn_front_page", count, offset, chunk_size);
+        traced("hn_front_page", span, async {
+            let formatter = match formatter_for(format.as_deref(), &self.format_options) {
+                Ok(formatter) => formatter,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+            let post_type = match PostType::parse(post_type.as_deref()) {
+                Ok(post_type) => post_type,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+
+            let limit = offset + post_type.candidate_pool(count);
+            let ids = match self.hn_client.get_front_page_stories(Some(limit)).await {
+                Ok(ids) => ids.into_iter().skip(offset).collect::<Vec<_>>(),
+                Err(e) => return Err(format!("Error fetching front page stories: {}", e)),
+            };
+
+            let stories = match self
+                .hn_client
+                .get_stories_details(ids.clone(), Some(chunk_size))
+                .await
+            {
+                Ok(stories) => stories,
+                Err(e) => return Err(format!("Error fetching front page stories: {}", e)),
+            };
+
+            let (mut stories, missing) = order_stories(&ids, stories);
+            let fetched = stories.len();
+            stories.retain(|story| post_type.matches(story));
+            stories.truncate(count);
+            let mut result = format_list_within(formatter.as_ref(), &stories, self.max_response_bytes);
+            if let Some(note) = shortfall_note(fetched + missing.len(), fetched) {
+                result.push_str(&formatter.note(&note));
+            }
+            partial(result, !missing.is_empty())
+        })
+        .await
+    }
+
+    #[tool(description = "Retrieves complete details of a specific Hacker News (HN is the common abbreviation for Hacker News) story by its unique ID. Returns all available information including title, URL, text, author, score, date, and comment count. Use this when you have a specific story ID and need to fetch its contents. Example: `hn_story_by_id(id=39617316)` returns the full details of that specific story ('Show HN: GPT-4o 10x faster for me using Alt+Enter vs Enter'). For link posts without text, `{\"name\": \"hn_story_by_id\", \"arguments\": {\"id\": 39617316, \"fetch_article\": true}}` also fetches the linked page and appends an 'Article:' line with the start of its text. To see who is in the discussion without the comment text: `{\"name\": \"hn_story_by_id\", \"arguments\": {\"id\": 39617316, \"with_commenters\": true}}` appends a line like 'Commenters (3): alice (2), bob, carol'.")]
+    async fn hn_story_by_id(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Numeric ID of the Hacker News story to fetch. Every HN story has a unique ID which can be found in story listings or URLs. Example: 39617316 (a Show HN post about GPT-4o) or 39617842 (an Ask HN post about productivity tools). These IDs are visible in the output of other HN tool functions or can be found in HN URLs.")]
+        id: u32,
+
+        #[tool(param)]
+        #[schemars(description = "Also fetch the page the story links to and append an 'Article:' line with the first ~500 characters of its readable text (default false). Only HTML pages are read, with a 10 second timeout; PDFs and other files are skipped with a note. Example: true to see what a link-only post is about.")]
+        fetch_article: Option<bool>,
+
+        #[tool(param)]
+        #[schemars(description = "Also list the distinct authors of the story's top-level comments, with a count for those who wrote several, without the comment text (default false). Example: true for a quick look at who is taking part in a discussion.")]
+        with_commenters: Option<bool>,
+    ) -> String {
+        let fetch_article = fetch_article.unwrap_or(false);
+        let with_commenters = with_commenters.unwrap_or(false);
+
+        let span = info_span!("hn_story_by_id", id, fetch_article, with_commenters);
+        traced("hn_story_by_id", span, async {
+            let story = match self.hn_client.get_story_details(id).await {
+                Ok(story) => story,
+                Err(e) => return Err(format!("Error fetching story with ID {}: {}", id, e)),
+            };
+            let mut result = client::HnClient::format_story_with(&story, &self.format_options);
+            let mut failed = false;
+            if fetch_article && !story.url.is_empty() {
+                match self.hn_client.fetch_article_excerpt(&story.url).await {
+                    Ok(excerpt) => result.push_str(&format!("Article: {}\n", excerpt)),
+                    Err(e) => {
+                        failed = true;
+                        result.push_str(&format!("(Could not fetch the article: {})\n", e));
+                    }
+                }
+            }
+            if with_commenters {
+                match self.hn_client.get_commenters(&story).await {
+                    Ok(commenters) => result.push_str(&client::HnClient::format_commenters(&commenters)),
+                    Err(e) => {
+                        failed = true;
+                        result.push_str(&format!("(Could not fetch the commenters: {})\n", e));
+                    }
+                }
+            }
+            partial(result, failed)
+        })
+        .await
+    }
+
+    #[tool(description = "Retrieves complete details of several Hacker News (HN is the common abbreviation for Hacker News) stories by ID in a single call. Returns each story's title, URL, text, author, score, date, and comment count, separated by '---', in the same order as the requested IDs (not re-sorted by score). Stories are fetched concurrently; IDs that cannot be fetched are reported at the end. Use this instead of calling hn_story_by_id repeatedly when you already have a list of IDs. Example usage: `{\"name\": \"hn_stories_by_ids\", \"arguments\": {\"ids\": [39617316, 39617052, 39618653]}}`. With more parallelism: `{\"name\": \"hn_stories_by_ids\", \"arguments\": {\"ids\": [39617316, 39617842], \"chunk_size\": 2}}`")]
+    async fn hn_stories_by_ids(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "List of numeric Hacker News story IDs to fetch (at most 30 IDs unless the server was started with a different --max-story-count). Duplicates are ignored and the output follows the order given here. Example: [39617316, 39617052, 39618653] using IDs from the 'ID:' lines of other HN tools.")]
+        ids: Vec<u32>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Higher values may speed up retrieval but increase API load. Example: 10 when fetching many IDs at once.")]
+        chunk_size: Option<usize>,
+    ) -> String {
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+
+        let span = info_span!("hn_stories_by_ids", count = ids.len(), chunk_size);
+        traced("hn_stories_by_ids", span, async {
+            if ids.is_empty() {
+                return Err("Error: at least one story ID is required".to_string());
+            }
+            if ids.len() > self.max_story_count {
+                return Err(format!(
+                    "Error: at most {} story IDs can be fetched at once ({} given)",
+                    self.max_story_count,
+                    ids.len()
+                ));
+            }
+
+            let stories = match self
+                .hn_client
+                .get_stories_details(ids.clone(), Some(chunk_size))
+                .await
+            {
+                Ok(stories) => stories,
+                Err(e) => return Err(format!("Error fetching stories: {}", e)),
+            };
+            info!("Fetched details for {} of {} stories", stories.len(), ids.len());
+
+            // Restore the caller's order; stories may come back in any order
+            let (stories, missing) = order_stories(&ids, stories);
+            let mut result = stories
+                .iter()
+                .map(|story| client::HnClient::format_story_with(story, &self.format_options))
+                .collect::<Vec<_>>()
+                .join(&self.format_options.joiner());
+            if !missing.is_empty() {
+                let missing = missing.iter().map(u32::to_string).collect::<Vec<_>>().join(", ");
+                result.push_str(&format!("\n(Could not fetch stories with IDs: {})\n", missing));
+            }
+            partial(result, !missing.is_empty())
+        })
+        .await
+    }
+
+    #[tool(description = "Warms this server's story cache: fetches the given Hacker News (HN is the common abbreviation for Hacker News) story IDs concurrently and keeps them in the cache, so later hn_story_by_id, hn_stories_by_ids and feed calls for them return without waiting on the HN API. Returns only a short summary such as 'Prefetched 12 stories, failed 1 (IDs: 39617999)', not the stories. Use it ahead of time in latency-sensitive sessions. Example usage: `{\"name\": \"hn_prefetch\", \"arguments\": {\"ids\": [39617316, 39617052, 39618653]}}`")]
+    async fn hn_prefetch(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Numeric Hacker News story IDs to load into the cache (at most 100 unless the server was started with a different --max-scan-count). Duplicates are ignored. Example: [39617316, 39617052].")]
+        ids: Vec<u32>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to fetch in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Example: 10 for a long list.")]
+        chunk_size: Option<usize>,
+    ) -> String {
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+
+        let span = info_span!("hn_prefetch", count = ids.len(), chunk_size);
+        traced("hn_prefetch", span, async {
+            if !self.hn_client.is_cache_enabled() {
+                return Err("Error: the story cache is disabled on this server (--no-cache), so there is nothing to prefetch into".to_string());
+            }
+            if ids.is_empty() {
+                return Err("Error: at least one story ID is required".to_string());
+            }
+            if ids.len() > self.max_scan_count {
+                return Err(format!(
+                    "Error: at most {} story IDs can be prefetched at once ({} given)",
+                    self.max_scan_count,
+                    ids.len()
+                ));
+            }
+
+            let stories = match self
+                .hn_client
+                .get_stories_details(ids.clone(), Some(chunk_size))
+                .await
+            {
+                Ok(stories) => stories,
+                Err(e) => return Err(format!("Error prefetching stories: {}", e)),
+            };
+            let (stories, missing) = order_stories(&ids, stories);
+            info!("Prefetched {} stories, {} failed", stories.len(), missing.len());
+
+            let mut result = format!(
+                "Prefetched {} {}, failed {}",
+                stories.len(),
+                if stories.len() == 1 { "story" } else { "stories" },
+                missing.len()
+            );
+            let failed = !missing.is_empty();
+            if failed {
+                let missing = missing.iter().map(u32::to_string).collect::<Vec<_>>().join(", ");
+                result.push_str(&format!(" (IDs: {})", missing));
+            }
+            partial(result, failed)
+        })
+        .await
+    }
+
+    #[tool(description = "Checks how a Hacker News (HN is the common abbreviation for Hacker News) story's score and comment count changed since this server last read it. Fetches the story fresh, compares it with the cached copy and reports the changes, e.g. 'Score: 120 (+15)' and 'Comments: 40 (+8)', then updates the cache so the next call measures from now. If the story was not cached (or the cache is disabled), only the current values are shown. Useful for watching a story over time. Example usage: `{\"name\": \"hn_story_delta\", \"arguments\": {\"id\": 39617316}}`")]
+    async fn hn_story_delta(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Numeric ID of the Hacker News story to check, as shown in the 'ID:' line of other HN tools. Example: 39617316.")]
+        id: u32,
+    ) -> String {
+        let span = info_span!("hn_story_delta", id);
+        traced("hn_story_delta", span, async {
+            let (previous, story) = match self.hn_client.refresh_story_details(id).await {
+                Ok(stories) => stories,
+                Err(e) => return Err(format!("Error fetching story with ID {}: {}", id, e)),
+            };
+
+            let delta = |current: u32, before: Option<u32>| match before {
+                Some(before) => format!("{} ({:+})", current, i64::from(current) - i64::from(before)),
+                None => current.to_string(),
+            };
+            let mut result = format!(
+                "Title: {}\nScore: {}\nComments: {}\nID: {}\n",
+                client::html::html_to_text(&story.title),
+                delta(story.score, previous.as_ref().map(|story| story.score)),
+                delta(story.number_of_comments, previous.as_ref().map(|story| story.number_of_comments)),
+                story.id
+            );
+            if previous.is_none() {
+                result.push_str("(No earlier copy cached; call again later to see changes)\n");
+            }
+            Ok(result)
+        })
+        .await
+    }
+
+    #[tool(description = "Developer tool: returns the raw JSON of any Hacker News (HN is the common abbreviation for Hacker News) item (story, comment, job, poll or poll option) exactly as the HN API returns it, pretty-printed. Always fetched fresh, never from the cache. Use this to debug unexpected output from the other HN tools; prefer hn_story_by_id for reading stories. Example usage: `{\"name\": \"hn_raw_item\", \"arguments\": {\"id\": 8863}}`")]
+    async fn hn_raw_item(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Numeric ID of the HN item to fetch. Example: 8863.")]
+        id: u32,
+    ) -> String {
+        let span = info_span!("hn_raw_item", id);
+        traced("hn_raw_item", span, async {
+            match self.hn_client.get_raw_item(id).await {
+                Ok(item) => serde_json::to_string_pretty(&item)
+                    .map_err(|e| format!("Error formatting item with ID {}: {}", id, e)),
+                Err(e) => Err(format!("Error fetching item with ID {}: {}", id, e)),
+            }
+        })
+        .await
+    }
+
+    #[tool(description = "Retrieves any Hacker News (HN is the common abbreviation for Hacker News) item by ID: stories are shown like hn_story_by_id, other items (comments, jobs, polls, poll options) with their type, title, URL, text, author, date, score and parent ID as available. With with_ancestors=true, a comment is preceded by its thread: the chain of parent items from the root story down to it, so it can be read in context. Example usage: `{\"name\": \"hn_item\", \"arguments\": {\"id\": 9224}}`. With the thread: `{\"name\": \"hn_item\", \"arguments\": {\"id\": 9224, \"with_ancestors\": true}}`")]
+    async fn hn_item(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Numeric ID of the HN item, e.g. from a comment permalink (news.ycombinator.com/item?id=...). Example: 9224.")]
+        id: u32,
+
+        #[tool(param)]
+        #[schemars(description = "Also walk the parent links up to the root story and list them, root first (default false). Very deep chains are cut off; parents that cannot be fetched are noted instead of failing the call. Example: true when reading a reply out of context.")]
+        with_ancestors: Option<bool>,
+    ) -> String {
+        let with_ancestors = with_ancestors.unwrap_or(false);
+
+        let span = info_span!("hn_item", id, with_ancestors);
+        traced("hn_item", span, async {
+            let item = match self.hn_client.get_item(id).await {
+                Ok(item) => item,
+                Err(e) => return Err(format!("Error fetching item with ID {}: {}", id, e)),
+            };
+            if !with_ancestors || item.parent.is_none() {
+                return Ok(client::HnClient::format_item(&item));
+            }
+
+            let (ancestors, note) = self
+                .hn_client
+                .get_ancestors(&item, client::MAX_ANCESTOR_DEPTH)
+                .await;
+            // A note with a full chain only reports the depth limit; a shorter one means a
+            // parent could not be fetched
+            let failed = note.is_some() && ancestors.len() < client::MAX_ANCESTOR_DEPTH;
+            let result = format!(
+                "{}---\n{}",
+                client::HnClient::format_thread_path(&ancestors, &item, note.as_deref()),
+                client::HnClient::format_item(&item)
+            );
+            partial(result, failed)
+        })
+        .await
+    }
+
+    #[tool(description = "Retrieves a Hacker News (HN is the common abbreviation for Hacker News) story together with its top-level comments in a single call. Returns the story details (title, URL, text, author, score, date, comment count) followed by a 'Top comments' section listing each comment's author, date, ID and text. HN does not expose comment scores, so comments are returned in the order HN ranks them on the discussion page. Use this for summarizing a discussion instead of calling hn_story_by_id and fetching comments separately. Example usage: `{\"name\": \"hn_story_with_comments\", \"arguments\": {\"id\": 39617316}}`. With more comments: `{\"name\": \"hn_story_with_comments\", \"arguments\": {\"id\": 39617842, \"top_n\": 10}}`")]
+    async fn hn_story_with_comments(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Numeric ID of the Hacker News story to fetch, as shown in the 'ID:' line of other HN tools or in HN URLs. Example: 39617316.")]
+        id: u32,
+
+        #[tool(param)]
+        #[schemars(description = "Number of top-level comments to include (default 5, max 20 unless the server was started with a different --max-comment-count). Comments are taken in HN's ranking order; deleted or flagged comments are skipped. Example: 3 for a quick sense of the discussion, 15 for a thorough summary.")]
+        top_n: Option<usize>,
+    ) -> String {
+        let top_n = top_n.unwrap_or(5).min(self.max_comment_count);
+
+        let span = info_span!("hn_story_with_comments", id, top_n);
+        traced("hn_story_with_comments", span, async {
+            let story = match self.hn_client.get_story_details(id).await {
+                Ok(story) => story,
+                Err(e) => return Err(format!("Error fetching story with ID {}: {}", id, e)),
+            };
+            let formatted_story = client::HnClient::format_story_with(&story, &self.format_options);
+
+            let comments = match self.hn_client.get_top_comments(&story, top_n).await {
+                Ok(comments) => comments,
+                Err(e) => return Err(format!("{}\nError fetching comments: {}", formatted_story, e)),
+            };
+            info!("Fetched {} comments for story {}", comments.len(), id);
+
+            if comments.is_empty() {
+                return Ok(format!("{}\nNo comments found", formatted_story));
+            }
+
+            let formatted_comments = comments
+                .iter()
+                .map(client::HnClient::format_comment)
+                .collect::<Vec<_>>()
+                .join(&self.format_options.joiner());
+
+            Ok(format!("{}\nTop comments:\n\n{}", formatted_story, formatted_comments))
+        })
+        .await
+    }
+
+    #[tool(description = "Returns the best comments of a Hacker News (HN is the common abbreviation for Hacker News) story: the first top-level comments in the order HN ranks them on the discussion page (HN does not expose comment scores, so its ranking is the best available signal). Each comment is numbered and shows only its author, permalink and text, without the story details, which makes this a short input for summarizing a discussion. Use hn_story_with_comments when you also need the story. Example usage: `{\"name\": \"hn_best_comments\", \"arguments\": {\"id\": 8863}}`. More comments: `{\"name\": \"hn_best_comments\", \"arguments\": {\"id\": 8863, \"count\": 8}}`")]
+    async fn hn_best_comments(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Numeric ID of the Hacker News story, as shown in the 'ID:' line of other HN tools or in HN URLs. Example: 8863.")]
+        id: u32,
+
+        #[tool(param)]
+        #[schemars(description = "Number of comments to return (default 3, max 20 unless the server was started with a different --max-comment-count). Deleted or flagged comments are skipped. Example: 5.")]
+        count: Option<usize>,
+    ) -> String {
+        let count = count.unwrap_or(3).clamp(1, self.max_comment_count);
+
+        let span = info_span!("hn_best_comments", id, count);
+        traced("hn_best_comments", span, async {
+            let story = match self.hn_client.get_story_details(id).await {
+                Ok(story) => story,
+                Err(e) => return Err(format!("Error fetching story with ID {}: {}", id, e)),
+            };
+
+            let comments = match self.hn_client.get_top_comments(&story, count).await {
+                Ok(comments) => comments,
+                Err(e) => return Err(format!("Error fetching comments for story {}: {}", id, e)),
+            };
+            info!("Fetched {} comments for story {}", comments.len(), id);
+
+            if comments.is_empty() {
+                return Ok(format!("No comments found for story {}", id));
+            }
+
+            let formatted_comments = comments
+                .iter()
+                .enumerate()
+                .map(|(index, comment)| client::HnClient::format_best_comment(index + 1, comment))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Ok(format!(
+                "Best comments on \"{}\" (ID: {}):\n\n{}",
+                client::html::html_to_text(&story.title),
+                id,
+                formatted_comments
+            ))
+        })
+        .await
+    }
+
+    #[tool(description = "Shows the results of a Hacker News (HN is the common abbreviation for Hacker News) poll: fetches every poll option, adds up their votes and lists each option with its vote count and percentage of the total, sorted by votes in descending order. Example usage: `{\"name\": \"hn_poll_results\", \"arguments\": {\"id\": 126809}}`")]
+    async fn hn_poll_results(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Numeric ID of the poll (an HN item of type 'poll'). Example: 126809.")]
+        id: u32,
+    ) -> String {
+        let span = info_span!("hn_poll_results", id);
+        traced("hn_poll_results", span, async {
+            match self.hn_client.get_poll_with_options(id).await {
+                Ok((poll, options)) => Ok(client::HnClient::format_poll_results(&poll, &options)),
+                Err(e) => Err(format!("Error fetching poll with ID {}: {}", id, e)),
+            }
+        })
+        .await
+    }
+
+    #[tool(description = "Finds the Hacker News (HN is the common abbreviation for Hacker News) story matching a URL and returns its complete details including title, URL, text, author, score, date, and comment count. Accepts either an HN discussion permalink (news.ycombinator.com/item?id=...) or the URL of an external article; external URLs are looked up with HN's Algolia search and, if submitted several times, the highest-scored submission is returned. Use this when you have a link and want its HN discussion; use hn_story_by_id when you already know the ID. Example usage: `{\"name\": \"hn_story_by_url\", \"arguments\": {\"url\": \"https://news.ycombinator.com/item?id=39617316\"}}`. For an external article: `{\"name\": \"hn_story_by_url\", \"arguments\": {\"url\": \"https://blog.rust-lang.org/2025/05/05/web-framework-developments.html\"}}`")]
+    async fn hn_story_by_url(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "The URL to look up. Either an HN permalink such as 'https://news.ycombinator.com/item?id=39617316' (the ID is extracted and fetched directly) or an external article URL such as 'https://github.com/structify/structify'. The scheme is optional; 'www.' and trailing slashes are ignored when matching external URLs.")]
+        url: String,
+    ) -> String {
+        let url = url.trim().to_string();
+
+        let span = info_span!("hn_story_by_url", url = truncate_chars(&url, MAX_LOGGED_CHARS));
+        traced("hn_story_by_url", span, async {
+            if url.is_empty() {
+                return Err("Error: url must not be empty".to_string());
+            }
+
+            let story_id = match client::parse_item_id_from_url(&url) {
+                Some(id) => id,
+                None => match self.hn_client.find_story_id_by_url(&url).await {
+                    Ok(Some(id)) => id,
+                    Ok(None) => return Ok(format!("No Hacker News story found for URL {}", url)),
+                    Err(e) => return Err(format!("Error searching for story with URL {}: {}", url, e)),
+                },
+            };
+
+            match self.hn_client.get_story_details(story_id).await {
+                Ok(story) => Ok(client::HnClient::format_story_with(&story, &self.format_options)),
+                Err(e) => Err(format!("Error fetching story with ID {}: {}", story_id, e)),
+            }
+        })
+        .await
+    }
+
+    #[tool(description = "Searches Hacker News (HN is the common abbreviation for Hacker News) comments for a term using HN's Algolia full-text search. Returns matching comments with the title and ID of the story they belong to, the comment author, date, a permalink to the comment, and the comment text, separated by '---' and ordered by search relevance (or newest first with sort='date'). Useful for researching community sentiment on a topic across many discussions. Example usage: `{\"name\": \"hn_search_comments\", \"arguments\": {\"query\": \"rust borrow checker\"}}`. Fewer results: `{\"name\": \"hn_search_comments\", \"arguments\": {\"query\": \"sqlite in production\", \"count\": 5}}`. Latest mentions: `{\"name\": \"hn_search_comments\", \"arguments\": {\"query\": \"bun\", \"sort\": \"date\"}}`. Who talks about a topic most, counted over all matches: `{\"name\": \"hn_search_comments\", \"arguments\": {\"query\": \"htmx\", \"facets\": \"author\"}}` starts the output with a line like 'Top authors: a (12), b (7)'")]
+    async fn hn_search_comments(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Search terms to look for in comment text. Example: 'rust borrow checker' or '\"local-first\"'.")]
+        query: String,
+
+        #[tool(param)]
+        #[schemars(description = "Number of comments to return (default 10, max 30 unless the server was started with a different --max-story-count). Example: 20 for a broader sample of opinions.")]
+        count: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Result order: 'relevance' (default) for the best matches, or 'date' for the newest comments first. Example: 'date' to see what people are saying right now.")]
+        sort: Option<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Comma-separated attributes to count all matching comments by, listed as a summary before the comments: 'author' and/or 'tags' (the story_<id> and author_<name> tags of each comment). Example: 'author' to see who discusses the topic most.")]
+        facets: Option<String>,
+    ) -> String {
+        let query = query.trim().to_string();
+        let count = count.unwrap_or(10).clamp(1, self.max_story_count);
+
+        let span = info_span!("hn_search_comments", query = truncate_chars(&query, MAX_LOGGED_CHARS), count, ?sort, ?facets);
+        traced("hn_search_comments", span, async {
+            // Algolia would match everything for an empty query
+            if query.is_empty() {
+                return Err("Error: query must not be empty".to_string());
+            }
+            let sort = match client::algolia::SearchSort::parse(sort.as_deref()) {
+                Ok(sort) => sort,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+            let facets = match client::algolia::SearchFacet::parse_list(facets.as_deref()) {
+                Ok(facets) => facets,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+
+            let response = match self.hn_client.search_comments_with_facets(&query, count, sort, &facets).await {
+                Ok(response) if response.hits.is_empty() => return Ok(format!("No comments found for {:?}", query)),
+                Ok(response) => response,
+                Err(e) => return Err(format!("Error searching comments for {:?}: {}", query, e)),
+            };
+            let mut sections = response
+                .hits
+                .iter()
+                .map(client::HnClient::format_comment_hit)
+                .collect::<Vec<_>>();
+            if !facets.is_empty() {
+                let summary = format!(
+                    "Matching comments: {}\n{}",
+                    response.nb_hits,
+                    client::HnClient::format_facets(&response.facets, &facets)
+                );
+                sections.insert(0, summary);
+            }
+            Ok(sections.join("\n---\n"))
+        })
+        .await
+    }
+
+    #[tool(description = "Finds Hacker News (HN is the common abbreviation for Hacker News) stories by tag combination with server-side filters on points and age, using HN's Algolia search. Returns complete details including title, URL, text, author, score, date, and comment count, sorted by score in descending order. Use this instead of fetching a feed and filtering it yourself, e.g. for 'Ask HN posts with at least 200 points from the last week'. Example usage: `{\"name\": \"hn_query\", \"arguments\": {\"tags\": \"ask_hn\", \"min_points\": 200, \"since_hours\": 168}}`. Stories by one author: `{\"name\": \"hn_query\", \"arguments\": {\"tags\": \"story,author_pg\", \"count\": 5}}`")]
+    async fn hn_query(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Algolia tag filter (default 'story'). Tags: 'story', 'ask_hn', 'show_hn', 'poll', 'front_page', 'author_<username>'. Comma-separated tags must all match; a parenthesized group matches any of them. Example: 'show_hn', 'story,author_pg' or '(ask_hn,show_hn)'.")]
+        tags: Option<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Only include stories with at least this many points. Example: 200.")]
+        min_points: Option<u32>,
+
+        #[tool(param)]
+        #[schemars(description = "Only include stories submitted in the last N hours. Example: 24 for the last day, 168 for the last week.")]
+        since_hours: Option<u64>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to fetch (default 10, max 30 unless the server was started with a different --max-story-count). Example: 20 for a broader result.")]
         count: Option<usize>,
 
         #[tool(param)]
-        #[schemars(description = "Number of stories to process in parallel (1-10, default 5). Higher values may speed up retrieval but increase API load. Example: 7 for balanced performance, 4 for slightly reduced load. Setting chunk_size=1 processes sequentially but puts minimal load on the API.")]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Example: 10 to fetch many stories quickly.")]
         chunk_size: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Output format: 'text' (default) for readable 'Title:/URL:' blocks separated by '---', 'json' for a JSON array of objects with id, title, url, text, by, score, time (Unix seconds) and comments, 'markdown' for a list of linked titles with a by/points/comments/age line (and quoted text for Ask posts), or 'compact' for one '[score] title — domain (id)' line per story. Example: 'json' when the result will be processed by code, 'markdown' for pasting into notes, 'compact' for scanning 30 stories at a glance.")]
+        format: Option<String>,
     ) -> String {
-        let count = count.unwrap_or(10).min(30);
-        let chunk_size = chunk_size.unwrap_or(5).clamp(1, 10);
+        let tags = tags
+            .as_deref()
+            .map(str::trim)
+            .filter(|tags| !tags.is_empty())
+            .unwrap_or("story")
+            .to_string();
+        let count = count.unwrap_or(10).clamp(1, self.max_story_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+        let created_after = since_hours.map(hours_ago);
 
-        match self
-            .get_hacker_news_stories(count, chunk_size, |client, limit| async move {
-                client.get_best_stories(Some(limit)).await
-            })
-            .await
-        {
-            Ok(result) => result,
-            Err(e) => format!("Error fetching best stories: {}", e),
-        }
+        let span = info_span!("hn_query", tags = truncate_chars(&tags, MAX_LOGGED_CHARS), ?min_points, ?since_hours, count, chunk_size);
+        traced("hn_query", span, async {
+            let formatter = match formatter_for(format.as_deref(), &self.format_options) {
+                Ok(formatter) => formatter,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+
+            match self
+                .get_hacker_news_stories(
+                    ListOptions { count, offset: 0, chunk_size, prefetch: false, post_type: PostType::All },
+                    formatter.as_ref(),
+                    |client, limit| async move {
+                        client.query_story_ids(&tags, min_points, created_after, limit).await
+                    },
+                )
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => Err(format!("Error querying stories: {}", e)),
+            }
+        })
+        .await
     }
 
-    #[tool(description = "Retrieves 'Ask HN' question posts from Hacker News (HN is the common abbreviation for Hacker News) where users ask the community for advice, opinions, or information. Returns complete details including title, text, author, score, date, and comment count. Particularly useful for finding discussions, questions, and community interactions. Results are sorted by score in descending order. Example: `hn_ask_stories(count=2)` might return questions like 'Ask HN: What productivity tools do you use in 2025?' (Score: 183, Comments: 207) and 'Ask HN: How are you using the new GPT-4o in your workflow?' (Score: 156, Comments: 142).")]
-    async fn hn_ask_stories(
+    #[tool(description = "Returns the best Hacker News (HN is the common abbreviation for Hacker News) stories submitted within the last N hours, e.g. 'the best of the last 24 hours', which the realtime best stories feed cannot answer precisely. Uses HN's Algolia search with server-side filters on submission time and points. Returns complete details including title, URL, text, author, score, date, and comment count, sorted by score in descending order. Example usage: `{\"name\": \"hn_top_since\", \"arguments\": {\"hours\": 24}}`. Only well-received stories of the past week: `{\"name\": \"hn_top_since\", \"arguments\": {\"hours\": 168, \"min_points\": 300, \"count\": 5}}`")]
+    async fn hn_top_since(
         &self,
         #[tool(param)]
-        #[schemars(description = "Number of stories to fetch (1-30, default 10). Controls how many Ask HN stories will be returned. Example: 12 will return the 12 highest-scoring Ask HN stories. Setting count=30 will give you the most comprehensive view of current community questions. Popular Ask HN posts often have many comments, making them valuable for research.")]
+        #[schemars(description = "Time window in hours; only stories submitted in the last `hours` hours are considered. Example: 24 for the last day, 168 for the last week.")]
+        hours: u64,
+
+        #[tool(param)]
+        #[schemars(description = "Only include stories with at least this many points. Example: 100.")]
+        min_points: Option<u32>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of stories to fetch (default 10, max 30 unless the server was started with a different --max-story-count). Example: 20 for a broader result.")]
         count: Option<usize>,
 
         #[tool(param)]
-        #[schemars(description = "Number of stories to process in parallel (1-10, default 5). Higher values may speed up retrieval but increase API load. Example: 6 for moderate concurrency. For Ask HN stories, which often contain more text content, a moderate chunk_size of 4-6 is generally optimal for balanced performance.")]
+        #[schemars(description = "Number of stories to process in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Example: 10 to fetch many stories quickly.")]
         chunk_size: Option<usize>,
+
+        #[tool(param)]
+        #[schemars(description = "Output format: 'text' (default) for readable 'Title:/URL:' blocks separated by '---', 'json' for a JSON array of objects with id, title, url, text, by, score, time (Unix seconds) and comments, 'markdown' for a list of linked titles with a by/points/comments/age line (and quoted text for Ask posts), or 'compact' for one '[score] title — domain (id)' line per story. Example: 'json' when the result will be processed by code, 'markdown' for pasting into notes, 'compact' for scanning 30 stories at a glance.")]
+        format: Option<String>,
     ) -> String {
-        let count = count.unwrap_or(10).min(30);
-        let chunk_size = chunk_size.unwrap_or(5).clamp(1, 10);
+        let count = count.unwrap_or(10).clamp(1, self.max_story_count);
+        let chunk_size = self.clamp_chunk_size(chunk_size);
 
-        match self
-            .get_hacker_news_stories(count, chunk_size, |client, limit| async move {
-                client.get_ask_stories(Some(limit)).await
-            })
-            .await
-        {
-            Ok(result) => result,
-            Err(e) => format!("Error fetching Ask HN stories: {}", e),
-        }
+        let span = info_span!("hn_top_since", hours, ?min_points, count, chunk_size);
+        traced("hn_top_since", span, async {
+            if hours == 0 {
+                return Err("Error: hours must be at least 1".to_string());
+            }
+            let formatter = match formatter_for(format.as_deref(), &self.format_options) {
+                Ok(formatter) => formatter,
+                Err(e) => return Err(format!("Error: {}", e)),
+            };
+            let created_after = hours_ago(hours);
+
+            // Without a search query Algolia ranks matches by points, so the first `count`
+            // hits are the best stories of the window
+            match self
+                .get_hacker_news_stories(
+                    ListOptions { count, offset: 0, chunk_size, prefetch: false, post_type: PostType::All },
+                    formatter.as_ref(),
+                    |client, limit| async move {
+                        client.query_story_ids("story", min_points, Some(created_after), limit).await
+                    },
+                )
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => Err(format!("Error fetching stories from the last {} hours: {}", hours, e)),
+            }
+        })
+        .await
     }
 
-    #[tool(description = "Retrieves 'Show HN' posts from Hacker News (HN is the common abbreviation for Hacker News) where users showcase their projects, websites, apps, or creations to get feedback from the community. Returns complete details including title, URL, text, author, score, date, and comment count. Ideal for discovering new projects and innovations. Results are sorted by score in descending order. Example: `hn_show_stories(count=2)` might return projects like 'Show HN: Structify – Convert unstructured text to structured data with AI' (URL: https://github.com/structify/structify) and 'Show HN: LocalLLM – Run powerful language models on consumer hardware' (URL: https://localllm.ai).")]
-    async fn hn_show_stories(
+    #[tool(description = "Lists what one Hacker News (HN is the common abbreviation for Hacker News) user recently posted, newest first, using HN's Algolia search. Stories are shown with their complete details (title, URL, text, author, score, date, comment count); comments with the title and ID of the story they belong to, date, permalink and text. Entries are separated by '---'. Example usage: `{\"name\": \"hn_by_author\", \"arguments\": {\"username\": \"pg\"}}`. Only stories: `{\"name\": \"hn_by_author\", \"arguments\": {\"username\": \"dang\", \"tags\": \"story\", \"count\": 5}}`")]
+    async fn hn_by_author(
         &self,
         #[tool(param)]
-        #[schemars(description = "Number of stories to fetch (1-30, default 10). Controls how many Show HN stories will be returned. Example: 10 will return the 10 highest-scoring Show HN stories. For discovering the widest range of new projects, try count=25, while for finding only the most popular showcases, try count=3. Show HN posts typically include project URLs and descriptions.")]
+        #[schemars(description = "HN username, as shown in the 'By:' line of other HN tools (letters, digits, '_' and '-'). Example: 'pg'.")]
+        username: String,
+
+        #[tool(param)]
+        #[schemars(description = "Number of submissions to return (default 10, max 30 unless the server was started with a different --max-story-count). Example: 20 for a longer history.")]
         count: Option<usize>,
 
         #[tool(param)]
-        #[schemars(description = "Number of stories to process in parallel (1-10, default 5). Higher values may speed up retrieval but increase API load. Example: 5 for default concurrency. Since Show HN posts often include links to external sites, a moderate chunk_size of 5 balances speed and API load effectively.")]
-        chunk_size: Option<usize>,
+        #[schemars(description = "Algolia tag filter for the kind of submission (default '(story,comment)' for both). Tags: 'story', 'comment', 'ask_hn', 'show_hn', 'poll'. Example: 'story' for submissions only, 'comment' for comments only.")]
+        tags: Option<String>,
     ) -> String {
-        let count = count.unwrap_or(10).min(30);
-        let chunk_size = chunk_size.unwrap_or(5).clamp(1, 10);
+        let username = username.trim().to_string();
+        let count = count.unwrap_or(10).clamp(1, self.max_story_count);
+        let tags = tags
+            .as_deref()
+            .map(str::trim)
+            .filter(|tags| !tags.is_empty())
+            .unwrap_or("(story,comment)")
+            .to_string();
 
-        match self
-            .get_hacker_news_stories(count, chunk_size, |client, limit| async move {
-                client.get_show_stories(Some(limit)).await
-            })
-            .await
-        {
-            Ok(result) => result,
-            Err(e) => format!("Error fetching Show HN stories: {}", e),
-        }
+        let span = info_span!(
+            "hn_by_author",
+            username = truncate_chars(&username, MAX_LOGGED_CHARS),
+            tags = truncate_chars(&tags, MAX_LOGGED_CHARS),
+            count
+        );
+        traced("hn_by_author", span, async {
+            if username.is_empty() {
+                return Err("Error: username must not be empty".to_string());
+            }
+            // The username becomes part of an Algolia tag, so reject anything HN would not allow
+            if !is_valid_username(&username) {
+                return Err(format!("Error: invalid HN username {:?}", username));
+            }
+
+            match self.hn_client.get_submissions_by_author(&username, &tags, count).await {
+                Ok(hits) if hits.is_empty() => {
+                    Ok(format!("No submissions found for user {} (tags: {})", username, tags))
+                }
+                Ok(hits) => Ok(hits
+                    .iter()
+                    .map(client::HnClient::format_submission_hit)
+                    .collect::<Vec<_>>()
+                    .join("\n---\n")),
+                Err(e) => Err(format!("Error fetching submissions of {}: {}", username, e)),
+            }
+        })
+        .await
     }
 
-    #[tool(description = "Retrieves complete details of a specific Hacker News (HN is the common abbreviation for Hacker News) story by its unique ID. Returns all available information including title, URL, text, author, score, date, and comment count. Use this when you have a specific story ID and need to fetch its contents. Example: `hn_story_by_id(id=39617316)` returns the full details of that specific story ('Show HN: GPT-4o 10x faster for me using Alt+Enter vs Enter').")]
-    async fn hn_story_by_id(
+    #[tool(description = "Looks up the karma of several Hacker News (HN is the common abbreviation for Hacker News) users at once and returns a compact 'username: karma' table sorted by karma in descending order. Usernames that do not exist or could not be fetched are listed in notes after the table instead of failing the call. Useful for leaderboards or comparing commenters. Example usage: `{\"name\": \"hn_users_karma\", \"arguments\": {\"usernames\": [\"pg\", \"dang\", \"patio11\"]}}`")]
+    async fn hn_users_karma(
         &self,
         #[tool(param)]
-        #[schemars(description = "Numeric ID of the Hacker News story to fetch. Every HN story has a unique ID which can be found in story listings or URLs. Example: 39617316 (a Show HN post about GPT-4o) or 39617842 (an Ask HN post about productivity tools). These IDs are visible in the output of other HN tool functions or can be found in HN URLs.")]
-        id: u32,
+        #[schemars(description = "HN usernames to look up (at most 30 unless the server was started with a different --max-story-count). Duplicates are ignored. Example: [\"pg\", \"dang\"].")]
+        usernames: Vec<String>,
+
+        #[tool(param)]
+        #[schemars(description = "Number of users to fetch in parallel (default 5, max 10 unless the server was started with a higher --max-concurrency). Example: 10 for a long list.")]
+        chunk_size: Option<usize>,
     ) -> String {
-        match self.hn_client.get_story_details(id).await {
-            Ok(story) => client::HnClient::format_story(&story),
-            Err(e) => format!("Error fetching story with ID {}: {}", id, e),
-        }
+        let chunk_size = self.clamp_chunk_size(chunk_size);
+        let mut seen = HashSet::new();
+        let usernames = usernames
+            .iter()
+            .map(|username| username.trim().to_string())
+            .filter(|username| !username.is_empty() && seen.insert(username.clone()))
+            .collect::<Vec<_>>();
+
+        let span = info_span!("hn_users_karma", count = usernames.len(), chunk_size);
+        traced("hn_users_karma", span, async {
+            if usernames.is_empty() {
+                return Err("Error: at least one username is required".to_string());
+            }
+            if usernames.len() > self.max_story_count {
+                return Err(format!(
+                    "Error: at most {} users can be looked up at once ({} given)",
+                    self.max_story_count,
+                    usernames.len()
+                ));
+            }
+
+            // Invalid names cannot exist on HN, so they are reported like missing users
+            let (valid, mut not_found): (Vec<_>, Vec<_>) =
+                usernames.into_iter().partition(|username| is_valid_username(username));
+            let mut users = Vec::new();
+            let mut failed = Vec::new();
+            for (username, result) in self.hn_client.get_users(&valid, chunk_size).await {
+                match result {
+                    Ok(Some(user)) => users.push(user),
+                    Ok(None) => not_found.push(username),
+                    Err(e) => {
+                        warn!("Error fetching user {}: {}", username, e);
+                        failed.push(username);
+                    }
+                }
+            }
+            info!("Fetched {} users ({} not found, {} failed)", users.len(), not_found.len(), failed.len());
+            let table = client::HnClient::format_karma_table(&users, &not_found, &failed);
+            partial(table, !failed.is_empty())
+        })
+        .await
     }
 
-    // Helper method to fetch stories using different strategies
+    // Clamp the requested chunk size to the configured concurrency ceiling
+    fn clamp_chunk_size(&self, chunk_size: Option<usize>) -> usize {
+        chunk_size.unwrap_or(5).clamp(1, self.max_concurrency)
+    }
 
     // Helper method to fetch stories using different strategies
     async fn get_hacker_news_stories<F, Fut>(
         &self,
-        count: usize,
-        chunk_size: usize,
+        options: ListOptions,
+        formatter: &dyn StoryFormatter,
         get_ids: F,
-    ) -> Result<String>
+    ) -> Result<ToolOutput>
+    where
+        F: FnOnce(client::HnClient, usize) -> Fut,
+        Fut: std::future::Future<Output = Result<Vec<u32>>>,
+    {
+        let list = self.list_stories(options, formatter, get_ids).await?;
+        Ok(partial(list.output, list.failed))
+    }
+
+    // `get_hacker_news_stories`, also reporting which candidates were shown
+    async fn list_stories<F, Fut>(&self, options: ListOptions, formatter: &dyn StoryFormatter, get_ids: F) -> Result<StoryList>
     where
         F: FnOnce(client::HnClient, usize) -> Fut,
         Fut: std::future::Future<Output = Result<Vec<u32>>>,
     {
-        // Get the story IDs from the specified endpoint
-        let story_ids = get_ids(self.hn_client.clone(), count).await?;
+        let ListOptions {
+            count,
+            offset,
+            chunk_size,
+            prefetch,
+            post_type,
+        } = options;
+
+        // Get the story IDs from the specified endpoint, skipping the first `offset`. A
+        // page holds more IDs than `count` when a post type filter will drop some of them.
+        let page_size = post_type.candidate_pool(count);
+        let page_count = if prefetch { 2 * page_size } else { page_size };
+        let story_ids = get_ids(self.hn_client.clone(), offset + page_count).await?;
+        let mut story_ids = story_ids.into_iter().skip(offset).collect::<Vec<_>>();
         info!("Retrieved {} story IDs", story_ids.len());
 
+        // Warm the cache for the following page while this one is fetched
+        if prefetch {
+            let next_page = story_ids.split_off(page_size.min(story_ids.len()));
+            self.hn_client.spawn_prefetch(next_page, Some(chunk_size));
+        }
+
         if story_ids.is_empty() {
-            return Ok("No stories found".to_string());
+            return Ok(StoryList {
+                output: formatter.format_list(&[]),
+                returned: Vec::new(),
+                withheld: Vec::new(),
+                failed: false,
+            });
         }
 
         // Fetch full details for each story using concurrent processing
+        let requested = story_ids.len();
         let stories = self
             .hn_client
-            .get_stories_details(story_ids, Some(chunk_size))
+            .get_stories_details(story_ids.clone(), Some(chunk_size))
             .await?;
         info!("Fetched details for {} stories", stories.len());
-
-        // Format the results
-        if stories.is_empty() {
-            return Ok("No stories found".to_string());
-        }
+        let fetched = stories.len();
 
         // Sort stories by score in descending order
         let mut sorted_stories = stories;
@@ -199,24 +1573,257 @@ impl HnRouter {
             b.score.cmp(&a.score) // Descending order
         });
 
-        let formatted_stories = sorted_stories
-            .iter()
-            .map(client::HnClient::format_story)
-            .collect::<Vec<_>>()
-            .join("\n---\n");
+        // Keep the requested kind of post
+        let fetched_ids = sorted_stories.iter().map(|story| story.id).collect::<HashSet<_>>();
+        sorted_stories.retain(|story| post_type.matches(story));
+        let kept = count_within(formatter, &sorted_stories[..count.min(sorted_stories.len())], self.max_response_bytes);
+        let mut withheld = sorted_stories[kept..].iter().map(|story| story.id).collect::<Vec<_>>();
+        withheld.extend(story_ids.iter().filter(|id| !fetched_ids.contains(id)));
+        sorted_stories.truncate(count);
 
-        Ok(formatted_stories)
+        // Format the results, explaining any stories lost to failed fetches
+        let mut formatted = format_list_within(formatter, &sorted_stories, self.max_response_bytes);
+        if let Some(note) = shortfall_note(requested, fetched) {
+            formatted.push_str(&formatter.note(&note));
+        }
+        Ok(StoryList {
+            output: formatted,
+            returned: sorted_stories[..kept].iter().map(|story| story.id).collect(),
+            withheld,
+            failed: fetched < requested,
+        })
     }
 }
 
-#[tool(tool_box)]
+// A rendered story list and the fate of its candidates
+struct StoryList {
+    output: String,
+    // Stories shown in `output`
+    returned: Vec<HackerNewsID>,
+    // Candidates that may match but were not shown: failed fetches and stories cut by `count`
+    // or the response size limit
+    withheld: Vec<HackerNewsID>,
+    // Some detail fetches failed; `output` ends with a note saying how many
+    failed: bool,
+}
+
+// Highest ID a poller can pass back as `since_id` without losing stories: the newest one
+// shown, but below every withheld candidate so those come back on the next poll
+fn polling_watermark(list: &StoryList) -> Option<HackerNewsID> {
+    let newest = list.returned.iter().copied().max()?;
+    Some(match list.withheld.iter().copied().min() {
+        Some(oldest_withheld) => newest.min(oldest_withheld - 1),
+        None => newest,
+    })
+}
+
+// Paging and filtering options of a story list request
+#[derive(Clone, Copy)]
+struct ListOptions {
+    count: usize,
+    offset: usize,
+    chunk_size: usize,
+    prefetch: bool,
+    post_type: PostType,
+}
+
+// Kind of submission the story list tools return (`post_type` parameter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PostType {
+    All,
+    Link,
+    Text,
+}
+
+impl PostType {
+    fn parse(post_type: Option<&str>) -> Result<Self> {
+        match post_type.map(str::trim).unwrap_or("all").to_ascii_lowercase().as_str() {
+            "" | "all" => Ok(Self::All),
+            "link" => Ok(Self::Link),
+            "text" => Ok(Self::Text),
+            other => bail!("Unsupported post_type '{}' (expected 'all', 'link' or 'text')", other),
+        }
+    }
+
+    // Text posts (Ask HN and the like) have no URL
+    fn matches(self, story: &HackerNewsStory) -> bool {
+        match self {
+            Self::All => true,
+            Self::Link => !story.url.is_empty(),
+            Self::Text => story.url.is_empty(),
+        }
+    }
+
+    // Feed positions to scan for `count` results; filtering drops some of them
+    fn candidate_pool(self, count: usize) -> usize {
+        match self {
+            Self::All => count,
+            Self::Link | Self::Text => count * 3,
+        }
+    }
+}
+
+// Unix timestamp `hours` hours before now, for Algolia's `created_at_i` filter
+fn hours_ago(hours: u64) -> i64 {
+    time::OffsetDateTime::now_utc().unix_timestamp() - i64::try_from(hours.saturating_mul(3600)).unwrap_or(i64::MAX)
+}
+
+// HN usernames consist of letters, digits, '_' and '-'
+fn is_valid_username(username: &str) -> bool {
+    !username.is_empty() && username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+// Text of a tool call, `Err` when all or part of it failed. Failed calls still show their
+// text (error messages, or partial results with a note), so only the metrics tell them apart.
+type ToolOutput = std::result::Result<String, String>;
+
+// Output of a call that may have partly failed, e.g. with some stories missing
+fn partial(output: String, failed: bool) -> ToolOutput {
+    if failed {
+        Err(output)
+    } else {
+        Ok(output)
+    }
+}
+
+// Note for results that came back short because some detail fetches failed
+fn shortfall_note(requested: usize, fetched: usize) -> Option<String> {
+    match requested.saturating_sub(fetched) {
+        0 => None,
+        1 => Some("1 story could not be fetched".to_string()),
+        failed => Some(format!("{} stories could not be fetched", failed)),
+    }
+}
+
+// Arrange stories in the order of `ids` (ignoring duplicates), and report the IDs
+// that have no matching story
+fn order_stories(ids: &[HackerNewsID], stories: Vec<HackerNewsStory>) -> (Vec<HackerNewsStory>, Vec<HackerNewsID>) {
+    let mut stories_by_id: HashMap<HackerNewsID, HackerNewsStory> =
+        stories.into_iter().map(|story| (story.id, story)).collect();
+    let mut seen = HashSet::new();
+    let mut ordered = Vec::with_capacity(ids.len());
+    let mut missing = Vec::new();
+    for id in ids.iter().filter(|id| seen.insert(**id)) {
+        match stories_by_id.remove(id) {
+            Some(story) => ordered.push(story),
+            None => missing.push(*id),
+        }
+    }
+    (ordered, missing)
+}
+
+// Tools return plain strings, which rmcp wraps in text blocks. Re-wrap JSON output in an
+// embedded resource with an `application/json` mime type so clients can tell it is JSON.
+// Error messages and other non-JSON text are left alone.
+fn json_result_to_resource(tool: &str, mut result: CallToolResult) -> CallToolResult {
+    for content in &mut result.content {
+        let Some(text) = content.as_text().map(|text| text.text.clone()) else {
+            continue;
+        };
+        if serde_json::from_str::<serde_json::Value>(&text).is_ok() {
+            *content = Content::resource(ResourceContents::TextResourceContents {
+                uri: format!("hn-mcp://tools/{}/result.json", tool),
+                mime_type: Some("application/json".to_string()),
+                text,
+            });
+        }
+    }
+    result
+}
+
+// Run a tool body inside its span, record latency and result size when it finishes,
+// and count the call for the metrics endpoint. The caller gets the text either way.
+async fn traced<F>(tool: &'static str, span: Span, body: F) -> String
+where
+    F: Future<Output = ToolOutput>,
+{
+    async move {
+        let start = Instant::now();
+        let (result, is_error) = match body.await {
+            Ok(result) => (result, false),
+            Err(result) => (result, true),
+        };
+        info!(
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            result_len = result.len(),
+            is_error,
+            "Tool call finished"
+        );
+        metrics().record_tool_call(tool, is_error);
+        result
+    }
+    .instrument(span)
+    .await
+}
+
+// `#[tool(tool_box)]` would list and dispatch every tool, so both are written out here
+// to leave out the tools disabled with `with_disabled_tools`
 impl ServerHandler for HnRouter {
+    async fn list_tools(
+        &self,
+        _: PaginatedRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, rmcp::Error> {
+        Ok(ListToolsResult {
+            next_cursor: None,
+            tools: Self::tool_box()
+                .list()
+                .into_iter()
+                .filter(|tool| !self.disabled_tools.contains(tool.name.as_ref()))
+                .collect(),
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::Error> {
+        if self.disabled_tools.contains(request.name.as_ref()) {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Error: tool '{}' is disabled on this server",
+                request.name
+            ))]));
+        }
+        let tool = request.name.to_string();
+        let json_output = tool == "hn_raw_item"
+            || request
+                .arguments
+                .as_ref()
+                .and_then(|arguments| arguments.get("format"))
+                .and_then(|format| format.as_str())
+                .is_some_and(|format| format.trim().eq_ignore_ascii_case("json"));
+        // Short random ID shared by every log line of this call, including the item fetches
+        // in spawned tasks, so concurrent calls can be told apart
+        let span = info_span!("tool_call", call_id = %format!("{:08x}", rand::random::<u32>()));
+        let result = Self::tool_box()
+            .call(ToolCallContext::new(self, request, context))
+            .instrument(span)
+            .await?;
+        Ok(if json_output { json_result_to_resource(&tool, result) } else { result })
+    }
+
     fn get_info(&self) -> ServerInfo {
+        let mut instructions = self.instructions.as_deref().unwrap_or(INSTRUCTIONS).to_string();
+        if !self.disabled_tools.is_empty() {
+            let mut disabled = self.disabled_tools.iter().map(String::as_str).collect::<Vec<_>>();
+            disabled.sort();
+            instructions.push_str(&format!(
+                "\n\nThe following tools are disabled on this server and are not available: {}",
+                disabled.join(", ")
+            ));
+        }
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
             capabilities: ServerCapabilities::builder().enable_tools().build(),
             server_info: Implementation::from_build_env(),
-            instructions: Some("Hacker News (HN) MCP Server providing access to content categories from Hacker News (HN), a popular tech-focused news aggregation site. Note: 'HN' is commonly used as an abbreviation for 'Hacker News' in function names and throughout this documentation. This server provides access to top, latest, best, Ask HN, and Show HN stories. Supports retrieval by story ID and concurrent processing for efficiency.
+            instructions: Some(instructions),
+        }
+    }
+}
+
+// Instructions returned by `get_info`, with one usage example per tool
+const INSTRUCTIONS: &str = "Hacker News (HN) MCP Server providing access to content categories from Hacker News (HN), a popular tech-focused news aggregation site. Note: 'HN' is commonly used as an abbreviation for 'Hacker News' in function names and throughout this documentation. This server provides access to top, latest, best, Ask HN, and Show HN stories. Supports retrieval by story ID or URL and concurrent processing for efficiency.
 
 ## Example Usage with Input/Output:
 
@@ -231,6 +1838,7 @@ impl ServerHandler for HnRouter {
    Date: 2025-05-04 15:43:20.000 +00:00:00
    Comments: 89
    ID: 39617316
+   HN: https://news.ycombinator.com/item?id=39617316
    ---
    Title: Find My Apple Watch
    URL: https://support.apple.com/en-us/108602
@@ -239,6 +1847,7 @@ impl ServerHandler for HnRouter {
    Date: 2025-05-04 14:03:11.000 +00:00:00
    Comments: 132
    ID: 39617052
+   HN: https://news.ycombinator.com/item?id=39617052
    ---
    Title: OpenAI has been training GPT-5 since December 2023
    URL: https://www.theverge.com/2025/5/4/24142756/openai-has-been-training-gpt-5-since-december-2023
@@ -247,6 +1856,7 @@ impl ServerHandler for HnRouter {
    Date: 2025-05-04 23:17:43.000 +00:00:00
    Comments: 74
    ID: 39618653
+   HN: https://news.ycombinator.com/item?id=39618653
    ```
 
 2. Get latest stories with parallelism:
@@ -260,6 +1870,7 @@ impl ServerHandler for HnRouter {
    Date: 2025-05-05 01:23:15.000 +00:00:00
    Comments: 28
    ID: 39619872
+   HN: https://news.ycombinator.com/item?id=39619872
    ---
    Title: The Future of Rust Web Development
    URL: https://blog.rust-lang.org/2025/05/05/web-framework-developments.html
@@ -268,6 +1879,7 @@ impl ServerHandler for HnRouter {
    Date: 2025-05-05 01:15:33.000 +00:00:00
    Comments: 19
    ID: 39619844
+   HN: https://news.ycombinator.com/item?id=39619844
    ```
    
 3. Find Ask HN discussions:
@@ -281,6 +1893,7 @@ impl ServerHandler for HnRouter {
    Date: 2025-05-04 18:27:41.000 +00:00:00
    Comments: 207
    ID: 39617842
+   HN: https://news.ycombinator.com/item?id=39617842
    ---
    Title: Ask HN: How are you using the new GPT-4o in your workflow?
    Text: Curious about real-world applications and how it's changing your daily tasks
@@ -289,6 +1902,7 @@ impl ServerHandler for HnRouter {
    Date: 2025-05-04 16:32:18.000 +00:00:00
    Comments: 142
    ID: 39617482
+   HN: https://news.ycombinator.com/item?id=39617482
    ```
 
 4. View Show HN projects:
@@ -303,6 +1917,7 @@ impl ServerHandler for HnRouter {
    Date: 2025-05-04 20:15:37.000 +00:00:00
    Comments: 48
    ID: 39618123
+   HN: https://news.ycombinator.com/item?id=39618123
    ---
    Title: Show HN: LocalLLM – Run powerful language models on consumer hardware
    URL: https://localllm.ai
@@ -312,6 +1927,7 @@ impl ServerHandler for HnRouter {
    Date: 2025-05-04 19:42:11.000 +00:00:00
    Comments: 62
    ID: 39618042
+   HN: https://news.ycombinator.com/item?id=39618042
    ```
 
 5. Lookup by specific ID:
@@ -325,7 +1941,363 @@ impl ServerHandler for HnRouter {
    Date: 2025-05-04 15:43:20.000 +00:00:00
    Comments: 89
    ID: 39617316
-   ```".to_string()),
-        }
+   HN: https://news.ycombinator.com/item?id=39617316
+   ```
+   With the start of the linked page: hn_story_by_id(id=39617316, fetch_article=true) adds an
+   `Article: ...` line, or a `(Could not fetch the article: ...)` note for non-HTML links
+   Who is in the thread: hn_story_by_id(id=39617316, with_commenters=true) adds
+   Commenters (3): alice (2), bob, carol
+
+6. Read a story with its top comments:
+   ```
+   Input: hn_story_with_comments(id=39617842, top_n=2)
+   Output:
+   Title: Ask HN: What productivity tools do you use in 2025?
+   Text: Looking for recommendations on the latest tools that have improved your workflow
+   By: productive_coder
+   Score: 183
+   Date: 2025-05-04 18:27:41.000 +00:00:00
+   Comments: 207
+   ID: 39617842
+   HN: https://news.ycombinator.com/item?id=39617842
+
+   Top comments:
+
+   By: vim_forever
+   Date: 2025-05-04 18:40:02.000 +00:00:00
+   ID: 39617901
+   Text: Still plain text files and a good editor. Nothing has beaten that for me.
+   ---
+   By: calendar_nerd
+   Date: 2025-05-04 18:52:37.000 +00:00:00
+   ID: 39617955
+   Text: Time-blocking in my calendar made the biggest difference this year.
+   ```
+
+7. Find the HN discussion of an article:
+   ```
+   Input: hn_story_by_url(url=\"https://twitter.com/tinkergoblin/status/1790778491434525211\")
+   Output:
+   Title: Show HN: GPT-4o 10x faster for me using Alt+Enter vs Enter 
+   URL: https://twitter.com/tinkergoblin/status/1790778491434525211
+   By: tinkergoblin
+   Score: 256
+   Date: 2025-05-04 15:43:20.000 +00:00:00
+   Comments: 89
+   ID: 39617316
+   HN: https://news.ycombinator.com/item?id=39617316
+   ```
+
+8. Get several stories by ID in one call (output keeps the requested order):
+   ```
+   Input: hn_stories_by_ids(ids=[39617316, 39617052])
+   Output:
+   Title: Show HN: GPT-4o 10x faster for me using Alt+Enter vs Enter 
+   URL: https://twitter.com/tinkergoblin/status/1790778491434525211
+   By: tinkergoblin
+   Score: 256
+   Date: 2025-05-04 15:43:20.000 +00:00:00
+   Comments: 89
+   ID: 39617316
+   HN: https://news.ycombinator.com/item?id=39617316
+   ---
+   Title: Rust 1.78.0
+   URL: https://blog.rust-lang.org/2025/05/02/Rust-1.78.0.html
+   By: steveklabnik
+   Score: 432
+   Date: 2025-05-02 14:15:23.000 +00:00:00
+   Comments: 143
+   ID: 39617052
+   HN: https://news.ycombinator.com/item?id=39617052
+   ```
+
+9. Get the stories on the HN homepage, in homepage order:
+   ```
+   Input: hn_front_page(count=2)
+   Output:
+   Title: Rust 1.78.0
+   URL: https://blog.rust-lang.org/2025/05/02/Rust-1.78.0.html
+   By: steveklabnik
+   Score: 432
+   Date: 2025-05-02 14:15:23.000 +00:00:00
+   Comments: 143
+   ID: 39617052
+   HN: https://news.ycombinator.com/item?id=39617052
+   ---
+   Title: Show HN: I built a terminal-based spreadsheet in Rust
+   URL: https://github.com/example/rust-spreadsheet
+   By: rustdev
+   Score: 87
+   Date: 2025-05-02 17:02:11.000 +00:00:00
+   Comments: 21
+   ID: 39618653
+   HN: https://news.ycombinator.com/item?id=39618653
+   ```
+
+10. Find what commenters say about a topic:
+   ```
+   Input: hn_search_comments(query=\"rust borrow checker\", count=1)
+   Output:
+   Story: Rust 1.78.0 (ID: 39617052)
+   By: pcwalton
+   Date: 2025-05-02 15:01:44.000 +00:00:00
+   Link: https://news.ycombinator.com/item?id=39617190
+   Text: The borrow checker improvements in this release remove a lot of the friction I used to hit with closures.
+   ```
+   Newest comments first: hn_search_comments(query=\"rust borrow checker\", sort=\"date\")
+   Who comments on a topic most: hn_search_comments(query=\"htmx\", facets=\"author\") starts with
+   Matching comments: 1843
+   Top authors: recursivedoubts (41), pier25 (12), yawaramin (9)
+
+11. Get story lists as JSON for further processing:
+   ```
+   Input: hn_top_stories(count=1, format=\"json\")
+   Output:
+   [{\"by\":\"steveklabnik\",\"comments\":143,\"hn_url\":\"https://news.ycombinator.com/item?id=39617052\",\"id\":39617052,\"score\":432,\"text\":null,\"time\":1746195323,\"title\":\"Rust 1.78.0\",\"url\":\"https://blog.rust-lang.org/2025/05/02/Rust-1.78.0.html\"}]
+   ```
+
+12. Get story lists as markdown for notes or chat:
+   ```
+   Input: hn_ask_stories(count=1, format=\"markdown\")
+   Output:
+   - [Ask HN: What productivity tools do you use in 2025?](https://news.ycombinator.com/item?id=39617842)
+     *by curious_dev · 183 points · 207 comments · 5 hours ago*
+
+     > I'm looking to improve my workflow. What tools are you using?
+   ```
+
+13. Get a compact score leaderboard of a feed:
+   ```
+   Input: hn_story_scores(feed=\"top\", count=3)
+   Output:
+   score  title (id)
+     432  Rust 1.78.0 (39617052)
+     256  Show HN: GPT-4o 10x faster for me using Alt+Enter vs Enter (39617316)
+      87  Show HN: I built a terminal-based spreadsheet in Rust (39618653)
+   ```
+
+14. See which websites dominate the top stories:
+   ```
+   Input: hn_trending_domains(count=10)
+   Output:
+   Domains among the top 10 stories:
+     4  github.com (IDs: 39618653, 39617911, 39619002, 39618420)
+     2  blog.rust-lang.org (IDs: 39617052, 39618877)
+     1  twitter.com (IDs: 39617316)
+     3  [text posts without a URL]
+   ```
+
+15. Inspect the raw API data of an item when debugging:
+   ```
+   Input: hn_raw_item(id=8863)
+   Output:
+   {
+     \"by\": \"dhouston\",
+     \"descendants\": 71,
+     \"id\": 8863,
+     \"kids\": [9224, 8917],
+     \"score\": 111,
+     \"time\": 1175714200,
+     \"title\": \"My YC app: Dropbox - Throw away your USB drive\",
+     \"type\": \"story\",
+     \"url\": \"http://www.getdropbox.com/u/2/screencast.html\"
+   }
+   ```
+
+16. Watch how a story's score and comment count change between calls:
+   ```
+   Input: hn_story_delta(id=39617316)
+   Output:
+   Title: Show HN: GPT-4o 10x faster for me using Alt+Enter vs Enter 
+   Score: 271 (+15)
+   Comments: 97 (+8)
+   ID: 39617316
+   ```
+
+17. Find stories by tag with point and age filters, without fetching a whole feed:
+   ```
+   Input: hn_query(tags=\"ask_hn\", min_points=200, since_hours=168, count=1)
+   Output:
+   Title: Ask HN: What productivity tools do you use in 2025?
+   Text: Curious what everyone's daily setup looks like.
+   By: throwaway_dev
+   Score: 483
+   Date: 2025-04-30 09:12:45.000 +00:00:00
+   Comments: 412
+   ID: 39571204
+   HN: https://news.ycombinator.com/item?id=39571204
+   ```
+
+18. Get a sectioned briefing of the top, Ask HN and Show HN feeds in one call:
+   ```
+   Input: hn_digest(count_per_feed=1)
+   Output:
+   ## Top
+
+   Title: Show HN: GPT-4o 10x faster for me using Alt+Enter vs Enter 
+   URL: https://twitter.com/tinkergoblin/status/1790778491434525211
+   By: tinkergoblin
+   Score: 256
+   Date: 2025-05-04 15:43:20.000 +00:00:00
+   Comments: 89
+   ID: 39617316
+   HN: https://news.ycombinator.com/item?id=39617316
+
+   ## Ask HN
+
+   Title: Ask HN: What productivity tools do you use in 2025?
+   Text: Curious what everyone's daily setup looks like.
+   By: throwaway_dev
+   Score: 183
+   Date: 2025-05-04 08:12:45.000 +00:00:00
+   Comments: 207
+   ID: 39617842
+   HN: https://news.ycombinator.com/item?id=39617842
+
+   ## Show HN
+
+   Title: Show HN: I built a terminal-based spreadsheet in Rust
+   URL: https://github.com/example/tui-sheets
+   By: rustacean
+   Score: 87
+   Date: 2025-05-04 12:30:00.000 +00:00:00
+   Comments: 31
+   ID: 39618653
+   HN: https://news.ycombinator.com/item?id=39618653
+   ```
+
+19. Get only the best comments of a story, for summarizing the discussion:
+   ```
+   Input: hn_best_comments(id=8863, count=2)
+   Output:
+   Best comments on \"My YC app: Dropbox - Throw away your USB drive\" (ID: 8863):
+
+   1. BrandonM (https://news.ycombinator.com/item?id=9224)
+   I have a few qualms with this app: 1. For a Linux user, you can already build such a system yourself quite trivially.
+
+   2. dhouston (https://news.ycombinator.com/item?id=8917)
+   Thanks for the feedback! Syncing works across platforms, and you don't need to set anything up.
+   ```
+
+20. Tally the votes of a poll:
+   ```
+   Input: hn_poll_results(id=126809)
+   Output:
+   Poll: Poll: What would happen if News.YC had explicit support for polls? (ID: 126809)
+   Total votes: 104
+
+   1. It would be fun to vote on things. - 58 votes (55.8%)
+   2. Polls would be overused. - 31 votes (29.8%)
+   3. Nothing much. - 15 votes (14.4%)
+   ```
+
+21. Scan a long story list with one line per story:
+   ```
+   Input: hn_top_stories(count=3, format=\"compact\")
+   Output:
+   [256] Show HN: GPT-4o 10x faster for me using Alt+Enter vs Enter — twitter.com (39617316)
+   [214] Find My Apple Watch — support.apple.com (39617052)
+   [183] Ask HN: What productivity tools do you use in 2025? (39617842)
+   ```
+
+22. See what a user posted recently:
+   ```
+   Input: hn_by_author(username=\"pg\", count=2)
+   Output:
+   Story: Ask HN: The Arc Effect (ID: 121003)
+   By: pg
+   Date: 2008-02-22 3:30:12.0 +00:00:00
+   Link: https://news.ycombinator.com/item?id=121016
+   Text: Arc is a lot less popular than Lisp was in the 80s.
+   ---
+   Title: Why TV Lost
+   URL: http://paulgraham.com/convergence.html
+   By: pg
+   Score: 220
+   Date: 2009-03-26 15:04:20.0 +00:00:00
+   Comments: 95
+   ID: 532928
+   HN: https://news.ycombinator.com/item?id=532928
+   ```
+
+23. Compare the karma of several users:
+   ```
+   Input: hn_users_karma(usernames=[\"dang\", \"pg\", \"nosuchuser\"])
+   Output:
+   pg: 157316
+   dang: 45000
+
+   (not found: nosuchuser)
+   ```
+
+24. See whether the top stories are being discussed:
+   ```
+   Input: hn_comment_count_histogram(count=30)
+   Output:
+   Comment counts among the top 30 stories:
+     0-10 | ######## 8
+    11-50 | ########### 11
+   51-200 | ######## 8
+     201+ | ### 3
+
+   Most discussed: Ask HN: What productivity tools do you use in 2025? (412 comments, 183 points, ID: 39617842)
+   ```
+
+25. Get the best stories of the last day:
+   ```
+   Input: hn_top_since(hours=24, count=1)
+   Output:
+   Title: Rust 1.78.0
+   URL: https://blog.rust-lang.org/2025/05/02/Rust-1.78.0.html
+   By: steveklabnik
+   Score: 432
+   Date: 2025-05-02 14:15:23.000 +00:00:00
+   Comments: 143
+   ID: 39617052
+   HN: https://news.ycombinator.com/item?id=39617052
+   ```
+
+26. Load stories into the cache ahead of time:
+   ```
+   Input: hn_prefetch(ids=[39617316, 39617052, 39617999])
+   Output:
+   Prefetched 2 stories, failed 1 (IDs: 39617999)
+   ```
+
+27. Read a comment in the context of its thread:
+   ```
+   Input: hn_item(id=9300, with_ancestors=true)
+   Output:
+   Thread (root first):
+   1. Story: My YC app: Dropbox - Throw away your USB drive (ID: 8863)
+   2. pg: Nice work. Can't wait to try it. (ID: 9224)
+   3. This comment (ID: 9300)
+   ---
+   Type: comment
+   Text: Thanks!
+   By: dhouston
+   Date: 2007-04-04 19:43:20.0 +00:00:00
+   Parent: 9224
+   ID: 9300
+   HN: https://news.ycombinator.com/item?id=9300
+   ```";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_traced_counts_partial_failures_as_errors() {
+        let partial_output = "Title: Story\n(1 story could not be fetched)\n";
+        let output = traced("test_partial_failure", Span::none(), async {
+            partial(partial_output.to_string(), true)
+        })
+        .await;
+        assert_eq!(output, partial_output);
+        traced("test_partial_failure", Span::none(), async { Ok("Title: Story\n".to_string()) }).await;
+
+        let rendered = metrics().render();
+        assert!(rendered.contains("hn_mcp_tool_calls_total{tool=\"test_partial_failure\"} 2\n"));
+        assert!(rendered.contains("hn_mcp_tool_errors_total{tool=\"test_partial_failure\"} 1\n"));
     }
 }
@@ -0,0 +1,60 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Cut `s` to at most `max_chars` characters without splitting a character or a
+/// grapheme cluster (an emoji with modifiers, a letter with combining marks). A
+/// cluster that would straddle the limit is dropped whole.
+pub fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    let mut chars = 0;
+    let mut end = 0;
+    for (start, grapheme) in s.grapheme_indices(true) {
+        chars += grapheme.chars().count();
+        if chars > max_chars {
+            break;
+        }
+        end = start + grapheme.len();
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_chars_ascii() {
+        assert_eq!(truncate_chars("hacker news", 6), "hacker");
+        assert_eq!(truncate_chars("hacker news", 100), "hacker news");
+        assert_eq!(truncate_chars("hacker news", 0), "");
+        assert_eq!(truncate_chars("", 5), "");
+    }
+
+    #[test]
+    fn test_truncate_chars_cjk() {
+        let text = "日本語のニュース";
+        assert_eq!(truncate_chars(text, 3), "日本語");
+        assert_eq!(truncate_chars(text, 3).chars().count(), 3);
+        assert_eq!(truncate_chars(text, 8), text);
+    }
+
+    #[test]
+    fn test_truncate_chars_emoji() {
+        assert_eq!(truncate_chars("🦀🦀🦀", 2), "🦀🦀");
+
+        // The family emoji is five chars joined into one grapheme; it is kept whole or not at all
+        let family = "👨‍👩‍👧 rust";
+        assert_eq!(family.chars().count(), 10);
+        assert_eq!(truncate_chars(family, 4), "");
+        assert_eq!(truncate_chars(family, 5), "👨‍👩‍👧");
+        assert_eq!(truncate_chars(family, 7), "👨‍👩‍👧 r");
+
+        // Flags are two regional indicator chars
+        assert_eq!(truncate_chars("🇯🇵🇺🇸", 3), "🇯🇵");
+    }
+
+    #[test]
+    fn test_truncate_chars_combining_marks() {
+        // "e" + combining acute accent stays together
+        assert_eq!(truncate_chars("cafe\u{301}!", 4), "caf");
+        assert_eq!(truncate_chars("cafe\u{301}!", 5), "cafe\u{301}");
+    }
+}
@@ -1,29 +1,394 @@
 use anyhow::Result;
-use rmcp::{Service, transport::sse_server::SseServer, ServerHandler, RoleServer};
-use std::net::SocketAddr;
-use tokio::task::JoinHandle;
+use axum::{
+    extract::{Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use futures::{channel::mpsc, SinkExt, Stream, StreamExt};
+use rmcp::{
+    model::{ClientJsonRpcMessage, ServerJsonRpcMessage},
+    RoleServer, ServerHandler, Service, ServiceExt,
+};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use tokio::{
+    sync::RwLock,
+    task::{JoinHandle, JoinSet},
+};
+use tokio_util::sync::CancellationToken;
 
-pub async fn serve<S>(service: S, port: u16) -> Result<JoinHandle<Result<()>>>
+use super::shutdown_signal;
+use crate::metrics::metrics;
+
+const SSE_PATH: &str = "/sse";
+const POST_PATH: &str = "/message";
+const METRICS_PATH: &str = "/metrics";
+
+type SessionId = Arc<str>;
+type SessionStore = Arc<RwLock<HashMap<SessionId, mpsc::Sender<ClientJsonRpcMessage>>>>;
+
+// The rmcp SSE server builds its axum router internally and does not allow
+// adding middleware, so we serve the same two endpoints ourselves.
+#[derive(Clone)]
+struct App {
+    sessions: SessionStore,
+    transport_tx: mpsc::UnboundedSender<SseTransport>,
+    // Message endpoint advertised to clients, including the path prefix
+    post_path: Arc<str>,
+    // Ends open SSE streams on shutdown, so graceful shutdown does not wait for idle clients
+    shutdown: CancellationToken,
+}
+
+struct SseTransport {
+    to_client: mpsc::Sender<ServerJsonRpcMessage>,
+    from_client: mpsc::Receiver<ClientJsonRpcMessage>,
+}
+
+// Removes the session from the store once the SSE stream is dropped
+struct SessionGuard {
+    session_id: SessionId,
+    sessions: SessionStore,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        let session_id = self.session_id.clone();
+        let sessions = self.sessions.clone();
+        tokio::spawn(async move {
+            sessions.write().await.remove(&session_id);
+        });
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostEventQuery {
+    session_id: String,
+}
+
+/// Optional features of the HTTP/SSE server
+#[derive(Default)]
+pub struct SseServerOptions {
+    /// Require `Authorization: Bearer <token>` on every request
+    pub auth_token: Option<String>,
+    /// Serve over HTTPS with this configuration
+    pub tls_config: Option<RustlsConfig>,
+    /// Expose Prometheus metrics at `GET /metrics`
+    pub metrics: bool,
+    /// Base path for the SSE and message endpoints, e.g. `/hn` (see [`normalize_path_prefix`]).
+    /// Empty serves them at the root. `/metrics` always stays at the root.
+    pub path_prefix: String,
+}
+
+/// Turn a user-supplied prefix into `/segment[/segment...]` without a trailing slash,
+/// or an empty string for the root
+pub fn normalize_path_prefix(prefix: &str) -> String {
+    let prefix = prefix.trim().trim_matches('/');
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", prefix)
+    }
+}
+
+pub async fn serve<S>(
+    service: S,
+    addr: SocketAddr,
+    options: SseServerOptions,
+) -> Result<JoinHandle<Result<()>>>
 where
     S: Service<RoleServer> + ServerHandler + Clone + Send + Sync + 'static,
 {
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    let sse_server = SseServer::serve(addr).await?;
-    let cancellation_token = sse_server.with_service(move || service.clone());
+    let cancellation_token = CancellationToken::new();
+    let (transport_tx, mut transport_rx) = mpsc::unbounded();
 
-    // Spawn a task that waits for Ctrl+C and then cancels the server
-    let handle = tokio::spawn(async move {
-        // Wait for Ctrl+C signal to gracefully shutdown
-        if let Err(e) = tokio::signal::ctrl_c().await {
-            tracing::error!("Failed to listen for ctrl+c: {}", e);
+    let path_prefix = normalize_path_prefix(&options.path_prefix);
+    let app = App {
+        sessions: Default::default(),
+        transport_tx,
+        post_path: format!("{}{}", path_prefix, POST_PATH).into(),
+        shutdown: cancellation_token.clone(),
+    };
+    let mut router = Router::new()
+        .route(&format!("{}{}", path_prefix, SSE_PATH), get(sse_handler))
+        .route(&format!("{}{}", path_prefix, POST_PATH), post(post_event_handler))
+        .with_state(app);
+
+    if options.metrics {
+        router = router.route(METRICS_PATH, get(metrics_handler));
+    }
+
+    // Reject unauthenticated requests before they reach the MCP handlers (or /metrics)
+    if let Some(token) = options.auth_token {
+        router = router.layer(middleware::from_fn_with_state(
+            Arc::<str>::from(token),
+            require_bearer_token,
+        ));
+    }
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let shutdown_token = cancellation_token.clone();
+    let server_task = match options.tls_config {
+        Some(tls_config) => {
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown_token.cancelled().await;
+                shutdown_handle.graceful_shutdown(None);
+            });
+
+            let server = axum_server::from_tcp_rustls(listener.into_std()?, tls_config)
+                .handle(handle)
+                .serve(router.into_make_service());
+            tokio::spawn(async move {
+                if let Err(e) = server.await {
+                    tracing::error!("SSE server shutdown with error: {}", e);
+                }
+            })
+        }
+        None => tokio::spawn(async move {
+            let server = axum::serve(listener, router).with_graceful_shutdown(async move {
+                shutdown_token.cancelled().await;
+            });
+            if let Err(e) = server.await {
+                tracing::error!("SSE server shutdown with error: {}", e);
+            }
+        }),
+    };
+
+    // Start an MCP service for every new SSE connection. The loop ends once the HTTP server
+    // has stopped and dropped its end of the channel; running sessions are then awaited.
+    let service_token = cancellation_token.clone();
+    let sessions_task = tokio::spawn(async move {
+        let mut sessions = JoinSet::new();
+        while let Some(transport) = transport_rx.next().await {
+            while sessions.try_join_next().is_some() {}
+            let service = service.clone();
+            let ct = service_token.child_token();
+            sessions.spawn(async move {
+                let sink = transport.to_client.sink_map_err(std::io::Error::other);
+                let server = service.serve_with_ct((sink, transport.from_client), ct).await?;
+                server.waiting().await?;
+                Ok::<(), anyhow::Error>(())
+            });
         }
-        
+        while sessions.join_next().await.is_some() {}
+    });
+
+    // Spawn a task that waits for Ctrl+C or SIGTERM, cancels the server and finishes once
+    // the server and its sessions have stopped
+    let handle = tokio::spawn(async move {
+        shutdown_signal().await;
+
         // Cancel the server
         tracing::info!("Shutting down server...");
         cancellation_token.cancel();
-        
+
+        server_task.await?;
+        sessions_task.await?;
+        tracing::info!("Server stopped");
         Ok(())
     });
 
     Ok(handle)
-}
\ No newline at end of file
+}
+
+async fn require_bearer_token(
+    State(token): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|provided| tokens_match(provided.trim(), &token));
+
+    if authorized {
+        next.run(request).await
+    } else {
+        tracing::warn!("Rejected request to {} without a valid bearer token", request.uri());
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "Unauthorized",
+        )
+            .into_response()
+    }
+}
+
+// Compare tokens without short-circuiting on the first differing byte
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        metrics().render(),
+    )
+}
+
+async fn sse_handler(
+    State(app): State<App>,
+) -> Result<Sse<impl Stream<Item = Result<Event, std::io::Error>>>, StatusCode> {
+    let session_id: SessionId = format!("{:016x}", rand::random::<u128>()).into();
+    tracing::info!("New SSE connection: {}", session_id);
+
+    let (from_client_tx, from_client_rx) = mpsc::channel(64);
+    let (to_client_tx, to_client_rx) = mpsc::channel(64);
+    app.sessions
+        .write()
+        .await
+        .insert(session_id.clone(), from_client_tx);
+
+    let transport = SseTransport {
+        to_client: to_client_tx,
+        from_client: from_client_rx,
+    };
+    if app.transport_tx.unbounded_send(transport).is_err() {
+        tracing::warn!("Failed to hand off SSE transport; server is shutting down");
+        app.sessions.write().await.remove(&session_id);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    let guard = SessionGuard {
+        session_id: session_id.clone(),
+        sessions: app.sessions.clone(),
+    };
+    let endpoint = Event::default()
+        .event("endpoint")
+        .data(format!("{}?sessionId={}", app.post_path, session_id));
+    let stream = futures::stream::once(futures::future::ok(endpoint))
+        .chain(to_client_rx.map(move |message| {
+            let _ = &guard;
+            match serde_json::to_string(&message) {
+                Ok(json) => Ok(Event::default().event("message").data(json)),
+                Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+            }
+        }))
+        .take_until(app.shutdown.cancelled_owned());
+
+    Ok(Sse::new(stream))
+}
+
+async fn post_event_handler(
+    State(app): State<App>,
+    Query(PostEventQuery { session_id }): Query<PostEventQuery>,
+    Json(message): Json<ClientJsonRpcMessage>,
+) -> Result<StatusCode, StatusCode> {
+    tracing::debug!("New message for session {}: {:?}", session_id, message);
+    let mut tx = {
+        let sessions = app.sessions.read().await;
+        sessions
+            .get(session_id.as_str())
+            .ok_or(StatusCode::NOT_FOUND)?
+            .clone()
+    };
+    if tx.send(message).await.is_err() {
+        tracing::error!("Failed to forward message to session {}", session_id);
+        return Err(StatusCode::GONE);
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    fn protected_router() -> Router {
+        Router::new()
+            .route(SSE_PATH, get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(
+                Arc::<str>::from("s3cret"),
+                require_bearer_token,
+            ))
+    }
+
+    async fn get_with_authorization(authorization: Option<&str>) -> Response {
+        let mut request = Request::builder().uri(SSE_PATH);
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        protected_router()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_normalize_path_prefix() {
+        assert_eq!(normalize_path_prefix(""), "");
+        assert_eq!(normalize_path_prefix("/"), "");
+        assert_eq!(normalize_path_prefix("hn"), "/hn");
+        assert_eq!(normalize_path_prefix("/hn/"), "/hn");
+        assert_eq!(normalize_path_prefix("a/b/"), "/a/b");
+        assert_eq!(normalize_path_prefix(" /hn "), "/hn");
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("s3cret", "s3cret"));
+        assert!(!tokens_match("s3creT", "s3cret"));
+        assert!(!tokens_match("s3cre", "s3cret"));
+        assert!(!tokens_match("s3crets", "s3cret"));
+        assert!(!tokens_match("", "s3cret"));
+    }
+
+    #[tokio::test]
+    async fn test_valid_bearer_token_is_accepted() {
+        assert_eq!(
+            get_with_authorization(Some("Bearer s3cret")).await.status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            get_with_authorization(Some("Bearer  s3cret "))
+                .await
+                .status(),
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_malformed_and_wrong_tokens_are_rejected() {
+        for authorization in [
+            None,
+            Some("s3cret"),
+            Some("Basic s3cret"),
+            Some("bearer s3cret"),
+            Some("Bearer"),
+            Some("Bearer wrong"),
+        ] {
+            let response = get_with_authorization(authorization).await;
+            assert_eq!(
+                response.status(),
+                StatusCode::UNAUTHORIZED,
+                "{:?}",
+                authorization
+            );
+            assert_eq!(
+                response
+                    .headers()
+                    .get(header::WWW_AUTHENTICATE)
+                    .map(|value| value.as_bytes()),
+                Some(&b"Bearer"[..]),
+                "{:?}",
+                authorization
+            );
+        }
+    }
+}
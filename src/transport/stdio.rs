@@ -1,18 +1,21 @@
-use crate::tools::hn::client::HnClient;
+use super::shutdown_signal;
 use crate::tools::hn::HnRouter;
 use anyhow::Result;
 use rmcp::transport::stdio;
 use rmcp::ServiceExt;
 
-pub async fn run_stdio_server() -> Result<()> {
-    // Create an instance of our search router with the API key
-    let service = HnRouter::new(HnClient::new());
-
+pub async fn run_stdio_server(service: HnRouter) -> Result<()> {
     // Use the rust-sdk stdio transport implementation
     let server = service.serve(stdio()).await?;
 
-    // Wait for the server to complete
-    server.waiting().await?;
+    // Wait for the client to close stdin, or for Ctrl+C / SIGTERM so the caller can still
+    // clean up (e.g. save the cache)
+    tokio::select! {
+        result = server.waiting() => {
+            result?;
+        }
+        _ = shutdown_signal() => {}
+    }
 
     Ok(())
 }
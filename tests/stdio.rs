@@ -0,0 +1,559 @@
+// End-to-end test of the stdio transport: spawns the `hn-mcp stdio` binary, performs the
+// MCP handshake with the rmcp client and calls a tool. The HN API is replaced by a mock
+// server through `HN_API_BASE_URL`, so no network access is needed.
+
+use rmcp::{
+    model::{CallToolRequestParam, ResourceContents},
+    transport::TokioChildProcess,
+    ServiceExt,
+};
+use serde_json::json;
+use tokio::process::Command;
+
+#[tokio::test]
+async fn test_stdio_handshake_list_and_call_tool() -> anyhow::Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let item = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"by":"dhouston","descendants":71,"id":8863,"kids":[9224,8917],"score":111,"time":1175714200,"title":"My YC app: Dropbox - Throw away your USB drive","type":"story","url":"http://www.getdropbox.com/u/2/screencast.html"}"#,
+        )
+        .create_async()
+        .await;
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command.arg("stdio").env("HN_API_BASE_URL", server.url());
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    let server_info = client.peer_info();
+    assert!(server_info.capabilities.tools.is_some());
+    assert!(server_info
+        .instructions
+        .as_deref()
+        .is_some_and(|text| text.contains("hn_top_stories")));
+
+    let tools = client.list_all_tools().await?;
+    let names = tools
+        .iter()
+        .map(|tool| tool.name.as_ref())
+        .collect::<Vec<_>>();
+    for expected in [
+        "hn_top_stories",
+        "hn_latest_stories",
+        "hn_story_by_id",
+        "hn_stories_by_ids",
+    ] {
+        assert!(
+            names.contains(&expected),
+            "missing tool {} in {:?}",
+            expected,
+            names
+        );
+    }
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_story_by_id".into(),
+            arguments: json!({ "id": 8863 }).as_object().cloned(),
+        })
+        .await?;
+    assert_ne!(result.is_error, Some(true));
+    let text = &result.content[0].as_text().expect("text content").text;
+    assert!(
+        text.starts_with("Title: My YC app: Dropbox - Throw away your USB drive\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("Score: 111\n"));
+    assert!(text.contains("ID: 8863\n"));
+    assert!(text.contains("HN: https://news.ycombinator.com/item?id=8863\n"));
+
+    client.cancel().await?;
+    item.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_disabled_tool_is_hidden_and_rejected() -> anyhow::Result<()> {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command
+        .args(["stdio", "--disable-tool", "hn_raw_item"])
+        .env("HN_API_BASE_URL", "http://127.0.0.1:9");
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    let instructions = client.peer_info().instructions.clone().unwrap_or_default();
+    assert!(instructions.contains("disabled on this server and are not available: hn_raw_item"));
+
+    let tools = client.list_all_tools().await?;
+    assert!(tools.iter().all(|tool| tool.name != "hn_raw_item"));
+    assert!(tools.iter().any(|tool| tool.name == "hn_top_stories"));
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_raw_item".into(),
+            arguments: json!({ "id": 8863 }).as_object().cloned(),
+        })
+        .await?;
+    assert_eq!(result.is_error, Some(true));
+    let text = &result.content[0].as_text().expect("text content").text;
+    assert_eq!(text, "Error: tool 'hn_raw_item' is disabled on this server");
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_post_type_filters_feed() -> anyhow::Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let feed = server
+        .mock("GET", "/topstories.json")
+        .with_header("content-type", "application/json")
+        .with_body("[8863,121003]")
+        .create_async()
+        .await;
+    let link = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"by":"dhouston","descendants":71,"id":8863,"score":111,"time":1175714200,"title":"My YC app: Dropbox - Throw away your USB drive","type":"story","url":"http://www.getdropbox.com/u/2/screencast.html"}"#,
+        )
+        .create_async()
+        .await;
+    let text = server
+        .mock("GET", "/item/121003.json")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"by":"tel","descendants":16,"id":121003,"score":25,"text":"Which one is better?","time":1203647620,"title":"Ask HN: The Arc Effect","type":"story"}"#,
+        )
+        .create_async()
+        .await;
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command.arg("stdio").env("HN_API_BASE_URL", server.url());
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_top_stories".into(),
+            arguments: json!({ "count": 1, "post_type": "text" })
+                .as_object()
+                .cloned(),
+        })
+        .await?;
+    let output = &result.content[0].as_text().expect("text content").text;
+    assert!(
+        output.starts_with("Title: Ask HN: The Arc Effect\n"),
+        "{}",
+        output
+    );
+    assert!(!output.contains("Dropbox"));
+
+    client.cancel().await?;
+    feed.assert_async().await;
+    link.assert_async().await;
+    text.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_digest_has_a_section_per_feed() -> anyhow::Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let mut mocks = Vec::new();
+    for feed in ["topstories", "askstories"] {
+        mocks.push(
+            server
+                .mock("GET", format!("/{}.json", feed).as_str())
+                .with_header("content-type", "application/json")
+                .with_body("[8863]")
+                .create_async()
+                .await,
+        );
+    }
+    // A failing feed is reported in its own section
+    mocks.push(
+        server
+            .mock("GET", "/showstories.json")
+            .with_status(500)
+            .create_async()
+            .await,
+    );
+    mocks.push(
+        server
+            .mock("GET", "/item/8863.json")
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"by":"dhouston","descendants":71,"id":8863,"score":111,"time":1175714200,"title":"My YC app: Dropbox - Throw away your USB drive","type":"story","url":"http://www.getdropbox.com/u/2/screencast.html"}"#,
+            )
+            // Both feeds may fetch it before either has cached it
+            .expect_at_least(1)
+            .create_async()
+            .await,
+    );
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command.arg("stdio").env("HN_API_BASE_URL", server.url());
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_digest".into(),
+            arguments: json!({ "count_per_feed": 1 }).as_object().cloned(),
+        })
+        .await?;
+    let output = &result.content[0].as_text().expect("text content").text;
+    assert!(
+        output.starts_with("## Top\n\nTitle: My YC app"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("\n## Ask HN\n\nTitle: My YC app"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("\n## Show HN\n\nError fetching Show HN stories: "),
+        "{}",
+        output
+    );
+
+    client.cancel().await?;
+    for mock in mocks {
+        mock.assert_async().await;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_json_output_is_a_json_resource() -> anyhow::Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let item = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"dhouston","id":8863,"score":111,"time":1175714200,"title":"My YC app","type":"story"}"#)
+        .create_async()
+        .await;
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command.arg("stdio").env("HN_API_BASE_URL", server.url());
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_raw_item".into(),
+            arguments: json!({ "id": 8863 }).as_object().cloned(),
+        })
+        .await?;
+    let resource = &result.content[0]
+        .as_resource()
+        .expect("resource content")
+        .resource;
+    let ResourceContents::TextResourceContents {
+        mime_type, text, ..
+    } = resource
+    else {
+        panic!("expected a text resource, got {:?}", resource);
+    };
+    assert_eq!(mime_type.as_deref(), Some("application/json"));
+    assert_eq!(serde_json::from_str::<serde_json::Value>(text)?["id"], 8863);
+
+    // Errors stay plain text
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_top_stories".into(),
+            arguments: json!({ "format": "json", "post_type": "bogus" })
+                .as_object()
+                .cloned(),
+        })
+        .await?;
+    let text = &result.content[0].as_text().expect("text content").text;
+    assert!(text.starts_with("Error: Unsupported post_type"), "{}", text);
+
+    client.cancel().await?;
+    item.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_max_story_count_limits_ids() -> anyhow::Result<()> {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command
+        .args(["stdio", "--max-story-count", "2"])
+        .env("HN_API_BASE_URL", "http://127.0.0.1:9");
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_stories_by_ids".into(),
+            arguments: json!({ "ids": [8863, 8917, 9224] }).as_object().cloned(),
+        })
+        .await?;
+    let text = &result.content[0].as_text().expect("text content").text;
+    assert_eq!(
+        text,
+        "Error: at most 2 story IDs can be fetched at once (3 given)"
+    );
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_prefetch_serves_later_reads_from_cache() -> anyhow::Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    let item = server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(
+            r#"{"by":"dhouston","descendants":71,"id":8863,"score":111,"time":1175714200,"title":"My YC app: Dropbox - Throw away your USB drive","type":"story","url":"http://www.getdropbox.com/u/2/screencast.html"}"#,
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    let missing = server
+        .mock("GET", "/item/1.json")
+        .with_header("content-type", "application/json")
+        .with_body("null")
+        .create_async()
+        .await;
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command.arg("stdio").env("HN_API_BASE_URL", server.url());
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_prefetch".into(),
+            arguments: json!({ "ids": [8863, 1, 8863] }).as_object().cloned(),
+        })
+        .await?;
+    let text = &result.content[0].as_text().expect("text content").text;
+    assert_eq!(text, "Prefetched 1 story, failed 1 (IDs: 1)");
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_story_by_id".into(),
+            arguments: json!({ "id": 8863 }).as_object().cloned(),
+        })
+        .await?;
+    let text = &result.content[0].as_text().expect("text content").text;
+    assert!(text.starts_with("Title: My YC app: Dropbox"), "{}", text);
+
+    client.cancel().await?;
+    item.assert_async().await;
+    missing.assert_async().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_blank_query_is_rejected() -> anyhow::Result<()> {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command
+        .arg("stdio")
+        .env("HN_API_BASE_URL", "http://127.0.0.1:9");
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    for (name, arguments, expected) in [
+        (
+            "hn_search_comments",
+            json!({ "query": "  \t" }),
+            "Error: query must not be empty",
+        ),
+        (
+            "hn_story_by_url",
+            json!({ "url": "" }),
+            "Error: url must not be empty",
+        ),
+        (
+            "hn_by_author",
+            json!({ "username": " " }),
+            "Error: username must not be empty",
+        ),
+    ] {
+        let result = client
+            .call_tool(CallToolRequestParam {
+                name: name.into(),
+                arguments: arguments.as_object().cloned(),
+            })
+            .await?;
+        let text = &result.content[0].as_text().expect("text content").text;
+        assert_eq!(text, expected, "{}", name);
+    }
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_serves_stories_from_read_only_cache_file() -> anyhow::Result<()> {
+    let dir = temp_dir::TempDir::new().map_err(anyhow::Error::msg)?;
+    let cache_file = dir.child("cache.json");
+    let seeded = r#"[{"id":8863,"title":"My YC app: Dropbox - Throw away your USB drive","url":"http://www.getdropbox.com/u/2/screencast.html","text":"","by":"dhouston","score":111,"created_at":1175714200,"number_of_comments":71,"comments":[]}]"#;
+    std::fs::write(&cache_file, seeded)?;
+
+    // The API is unreachable, so the story can only come from the cache file
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command
+        .arg("stdio")
+        .arg("--cache-file")
+        .arg(&cache_file)
+        .arg("--read-only-cache")
+        .env("HN_API_BASE_URL", "http://127.0.0.1:9");
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_story_by_id".into(),
+            arguments: json!({ "id": 8863 }).as_object().cloned(),
+        })
+        .await?;
+    let text = &result.content[0].as_text().expect("text content").text;
+    assert!(text.starts_with("Title: My YC app: Dropbox"), "{}", text);
+
+    client.cancel().await?;
+    assert_eq!(std::fs::read_to_string(&cache_file)?, seeded);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_instructions_file_replaces_built_in_instructions() -> anyhow::Result<()> {
+    let dir = temp_dir::TempDir::new().map_err(anyhow::Error::msg)?;
+    let instructions_file = dir.child("instructions.md");
+    std::fs::write(
+        &instructions_file,
+        "Only use HN tools for questions about startups.",
+    )?;
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command
+        .arg("stdio")
+        .arg("--instructions-file")
+        .arg(&instructions_file)
+        .args(["--disable-tool", "hn_raw_item"])
+        .env("HN_API_BASE_URL", "http://127.0.0.1:9");
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    let instructions = client.peer_info().instructions.clone().unwrap_or_default();
+    assert!(instructions.starts_with("Only use HN tools for questions about startups."));
+    assert!(instructions.contains("not available: hn_raw_item"));
+    assert!(!instructions.contains("Hacker News (HN) MCP Server"));
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stdio_latest_stories_watermark_keeps_withheld_stories() -> anyhow::Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/newstories.json")
+        .with_header("content-type", "application/json")
+        .with_body("[105,104,103,102,101]")
+        .create_async()
+        .await;
+    for (id, score, url) in [
+        (105, 1, true),
+        (104, 50, false),
+        (103, 10, true),
+        (102, 5, true),
+        (101, 3, false),
+    ] {
+        let url = if url {
+            format!(r#","url":"https://example.com/{}""#, id)
+        } else {
+            String::new()
+        };
+        server
+            .mock("GET", format!("/item/{}.json", id).as_str())
+            .with_header("content-type", "application/json")
+            .with_body(format!(
+                r#"{{"by":"pg","id":{},"score":{},"time":1175714200,"title":"Story {}","type":"story"{}}}"#,
+                id, score, id, url
+            ))
+            .create_async()
+            .await;
+    }
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_hn-mcp"));
+    command.arg("stdio").env("HN_API_BASE_URL", server.url());
+    let client = ().serve(TokioChildProcess::new(&mut command)?).await?;
+
+    // 105 is a link post but is cut by count, so the watermark must stay below it
+    let result = client
+        .call_tool(CallToolRequestParam {
+            name: "hn_latest_stories".into(),
+            arguments:
+                json!({ "since_id": 100, "count": 2, "post_type": "link", "format": "json" })
+                    .as_object()
+                    .cloned(),
+        })
+        .await?;
+    let resource = &result.content[0]
+        .as_resource()
+        .expect("resource content")
+        .resource;
+    let ResourceContents::TextResourceContents { text, .. } = resource else {
+        panic!("expected a text resource, got {:?}", resource);
+    };
+    let output = serde_json::from_str::<serde_json::Value>(text)?;
+    assert_eq!(output["newest_id"], 103);
+    let ids = output["stories"]
+        .as_array()
+        .expect("stories array")
+        .iter()
+        .map(|story| story["id"].clone())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![json!(103), json!(102)]);
+
+    client.cancel().await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_stdio_saves_cache_file_on_sigterm() -> anyhow::Result<()> {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/item/8863.json")
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"by":"dhouston","id":8863,"score":111,"time":1175714200,"title":"My YC app","type":"story"}"#)
+        .create_async()
+        .await;
+    let dir = temp_dir::TempDir::new().map_err(anyhow::Error::msg)?;
+    let cache_file = dir.child("cache.json");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hn-mcp"))
+        .arg("stdio")
+        .arg("--cache-file")
+        .arg(&cache_file)
+        .env("HN_API_BASE_URL", server.url())
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()?;
+    let transport = (
+        child.stdout.take().expect("stdout"),
+        child.stdin.take().expect("stdin"),
+    );
+    let client = ().serve(transport).await?;
+    client
+        .call_tool(CallToolRequestParam {
+            name: "hn_story_by_id".into(),
+            arguments: json!({ "id": 8863 }).as_object().cloned(),
+        })
+        .await?;
+
+    // Stop the server with stdin still open, as a service manager would
+    let pid = child.id().expect("child pid").to_string();
+    assert!(std::process::Command::new("kill")
+        .args(["-TERM", &pid])
+        .status()?
+        .success());
+    let status = tokio::time::timeout(std::time::Duration::from_secs(10), child.wait()).await??;
+    assert!(status.success(), "{}", status);
+
+    let saved = std::fs::read_to_string(&cache_file)?;
+    assert!(saved.contains(r#""id":8863"#), "{}", saved);
+    drop(client);
+    Ok(())
+}